@@ -0,0 +1,87 @@
+use sea_orm::{entity::prelude::*, InsertResult, IntoActiveModel, QueryOrder};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "exercise")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub exercise_id: Uuid,
+    pub user_id: Uuid,
+    pub title: String,
+    #[sea_orm(column_type = "Text")]
+    pub description: String,
+    pub function_name: String,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub args: Json,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub output: Json,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub comparison: Json,
+    #[sea_orm(column_type = "Text")]
+    pub solution: String,
+    pub solution_lang: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub starting_code: Option<String>,
+    pub generator_lang: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub generator: Option<String>,
+    pub checker_lang: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub checker: Option<String>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub generated_table: Json,
+    pub created: DateTime,
+    pub updated: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::UserId",
+        on_delete = "Cascade"
+    )]
+    User,
+    #[sea_orm(has_many = "super::test_case::Entity")]
+    TestCase,
+    #[sea_orm(has_many = "super::submission::Entity")]
+    Submission,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl Related<super::test_case::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TestCase.def()
+    }
+}
+
+impl Related<super::submission::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Submission.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub async fn get(db: &DatabaseConnection, exercise_id: Uuid) -> Result<Option<Self>, DbErr> {
+        Entity::find_by_id(exercise_id).one(db).await
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> Result<InsertResult<ActiveModel>, DbErr> {
+        Entity::insert(self.into_active_model()).exec(db).await
+    }
+
+    pub async fn list_by_user(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<Self>, DbErr> {
+        Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_desc(Column::Updated)
+            .all(db)
+            .await
+    }
+}
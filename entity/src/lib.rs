@@ -1,4 +1,7 @@
+pub mod exercise;
 pub mod google_user;
 pub mod sea_orm_active_enums;
 pub mod user;
-pub mod session;
\ No newline at end of file
+pub mod session;
+pub mod test_case;
+pub mod submission;
\ No newline at end of file
@@ -8,3 +8,24 @@ pub enum Account {
     #[sea_orm(string_value = "user")]
     User,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "submission_status")]
+pub enum SubmissionStatus {
+    #[sea_orm(string_value = "passed")]
+    Passed,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "error")]
+    Error,
+    #[sea_orm(string_value = "time_limit_exceeded")]
+    TimeLimitExceeded,
+    #[sea_orm(string_value = "memory_limit_exceeded")]
+    MemoryLimitExceeded,
+    #[sea_orm(string_value = "output_limit_exceeded")]
+    OutputLimitExceeded,
+    #[sea_orm(string_value = "compile_error")]
+    CompileError,
+    #[sea_orm(string_value = "checker_error")]
+    CheckerError,
+}
@@ -0,0 +1,79 @@
+use sea_orm::{entity::prelude::*, InsertResult, IntoActiveModel, QueryOrder};
+
+use crate::sea_orm_active_enums::SubmissionStatus;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "submission")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub submission_id: Uuid,
+    pub user_id: Uuid,
+    pub exercise_id: Uuid,
+    pub language: String,
+    #[sea_orm(column_type = "Text")]
+    pub code: String,
+    pub created: DateTime,
+    pub status: SubmissionStatus,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub results: Json,
+    #[sea_orm(column_type = "Text")]
+    pub stdout: String,
+    #[sea_orm(column_type = "Text")]
+    pub stderr: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::UserId",
+        on_delete = "Cascade"
+    )]
+    User,
+    #[sea_orm(
+        belongs_to = "super::exercise::Entity",
+        from = "Column::ExerciseId",
+        to = "super::exercise::Column::ExerciseId",
+        on_delete = "Cascade"
+    )]
+    Exercise,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl Related<super::exercise::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Exercise.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub async fn get(db: &DatabaseConnection, submission_id: Uuid) -> Result<Option<Self>, DbErr> {
+        Entity::find_by_id(submission_id).one(db).await
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> Result<InsertResult<ActiveModel>, DbErr> {
+        Entity::insert(self.into_active_model()).exec(db).await
+    }
+
+    /// Every attempt `user_id` has made on an exercise, newest first
+    pub async fn list_by_user(
+        db: &DatabaseConnection,
+        user_id: Uuid,
+        exercise_id: Uuid,
+    ) -> Result<Vec<Self>, DbErr> {
+        Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::ExerciseId.eq(exercise_id))
+            .order_by_desc(Column::Created)
+            .all(db)
+            .await
+    }
+}
@@ -0,0 +1,74 @@
+use sea_orm::{entity::prelude::*, InsertResult, IntoActiveModel, QueryOrder, TransactionTrait};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "test_case")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub test_case_id: Uuid,
+    pub exercise_id: Uuid,
+    pub position: i32,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub input: Json,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub output: Json,
+    pub hidden: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::exercise::Entity",
+        from = "Column::ExerciseId",
+        to = "super::exercise::Column::ExerciseId",
+        on_delete = "Cascade"
+    )]
+    Exercise,
+}
+
+impl Related<super::exercise::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Exercise.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub async fn get(db: &DatabaseConnection, test_case_id: Uuid) -> Result<Option<Self>, DbErr> {
+        Entity::find_by_id(test_case_id).one(db).await
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> Result<InsertResult<ActiveModel>, DbErr> {
+        Entity::insert(self.into_active_model()).exec(db).await
+    }
+
+    pub async fn list_by_exercise(
+        db: &DatabaseConnection,
+        exercise_id: Uuid,
+    ) -> Result<Vec<Self>, DbErr> {
+        Entity::find()
+            .filter(Column::ExerciseId.eq(exercise_id))
+            .order_by_asc(Column::Position)
+            .all(db)
+            .await
+    }
+
+    /// Replace every test case of an exercise with `cases`
+    pub async fn replace_all(
+        db: &DatabaseConnection,
+        exercise_id: Uuid,
+        cases: Vec<Self>,
+    ) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        Entity::delete_many()
+            .filter(Column::ExerciseId.eq(exercise_id))
+            .exec(&txn)
+            .await?;
+        if !cases.is_empty() {
+            Entity::insert_many(cases.into_iter().map(|c| c.into_active_model()))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await
+    }
+}
@@ -9,25 +9,35 @@ import type { functionArgsSchema } from '$src/routes/api/exec/schema';
 export type CodeFnReturn = { code: string; cursor: [number, number] | number };
 export type CodeFnDef = { args: z.infer<typeof functionArgsSchema>; output: string };
 export type CodeFn = (fns: { [key: string]: CodeFnDef }) => CodeFnReturn;
-export type LangInfo =
-	| {
-			lang: LanguageSupport;
-			type: 'markup' | 'config';
-	  }
-	| {
-			lang: LanguageSupport;
-			type: 'scripting' | 'compiled';
-			code: CodeFn;
-	  };
+/** How the editor handles a language. Everything else about it comes from `GET /langs`. */
+export type EditorLang = {
+	lang: LanguageSupport;
+	/** starting code for functions, for languages that exercises can be written in */
+	code?: CodeFn;
+};
+
+// the runner loads the code as a plain script, so functions are not exported
+function jsCode(fns: { [key: string]: CodeFnDef }): CodeFnReturn {
+	const entries = Object.entries(fns);
+	const code = entries
+		.map(
+			([name, { args }]) => `function ${name}(${args.map(({ arg }) => arg).join(', ')}) {\n\t\n}\n`
+		)
+		.join('\n');
+	const [name, { args }] = entries[0];
+	const pos = 9 + name.length + 1 + args.map(({ arg }) => arg).join(', ').length + 5;
+	return {
+		code: `\n${code}`,
+		cursor: pos + 1
+	};
+}
 
-export const langs: { [key: string]: LangInfo } = {
+export const langs: { [key: string]: EditorLang } = {
 	markdown: {
-		lang: markdown(),
-		type: 'markup'
+		lang: markdown()
 	},
 	python: {
 		lang: python(),
-		type: 'scripting',
 		code: (fns) => {
 			const entries = Object.entries(fns);
 			const code = entries
@@ -45,27 +55,13 @@ export const langs: { [key: string]: LangInfo } = {
 	},
 	javascript: {
 		lang: javascript(),
-		type: 'scripting',
-		code: (fns) => {
-			const entries = Object.entries(fns);
-			const code = entries
-				.map(
-					([name, { args }]) =>
-						`export function ${name}(${args.map(({ arg }) => arg).join(', ')}) {\n\t\n}\n`
-				)
-				.join('\n');
-			const [name, { args }] = entries[0];
-			const pos = 16 + name.length + 1 + args.map(({ arg }) => arg).join(', ').length + 5;
-			return {
-				code: `\n${code}`,
-				cursor: pos + 1
-			};
-		}
+		code: jsCode
+	},
+	typescript: {
+		lang: javascript({ typescript: true }),
+		code: jsCode
 	},
 	yaml: {
-		lang: yaml(),
-		type: 'config'
+		lang: yaml()
 	}
 };
-
-export const names: ['markdown', 'python', 'javascript', 'yaml'] = Object.keys(langs) as any;
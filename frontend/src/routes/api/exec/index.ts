@@ -21,8 +21,29 @@ type GenResult =
 			stderr: string;
 			stdout: string;
 	  }
+	| {
+			exit_code: undefined;
+			limit: 'TimeLimitExceeded' | 'MemoryLimitExceeded' | 'OutputLimitExceeded';
+			stderr: string;
+			stdout: string;
+	  }
+	| {
+			exit_code: undefined;
+			/** a generated case does not match the signature */
+			error: { kind: 'argument_count' | 'argument' | 'output'; case: number };
+			message: string;
+			stderr: string;
+			stdout: string;
+	  }
+	| {
+			exit_code: number;
+			/** everything the compiler printed */
+			diagnostics: string;
+	  }
 	| {
 			exit_code: number;
+			/** set if the program exited successfully without valid results */
+			message?: string;
 			stderr: string;
 			stdout: string;
 	  };
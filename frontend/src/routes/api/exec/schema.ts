@@ -1,4 +1,5 @@
-import { names, langs } from '$src/lib/components/editor/lang';
+import * as langs from '$src/routes/api/langs';
+import type { Lang } from '$src/routes/api/langs';
 import { sanitize_html } from '$src/lib/utils';
 import { typeSchema } from '../schema';
 import { longStringSchema } from '$src/routes/api/schema';
@@ -13,7 +14,19 @@ const literalSchema = z.union([
 ]);
 type Literal = z.infer<typeof literalSchema>;
 
-export const langSchema = z.enum(names);
+/**
+ * A language from `GET /langs` that `check` accepts. Any name passes while the
+ * list can't be fetched, like when rendering on the server, since the backend
+ * checks it again.
+ */
+const langSchema = (check: (lang: Lang) => boolean, message: string) =>
+	z.string().superRefine(async (name, ctx) => {
+		const all = await langs.list();
+		if (!all.length) return;
+		const lang = all.find((l) => l.name == name);
+		if (!lang) ctx.addIssue({ code: z.ZodIssueCode.custom, message: `Unknown language: ${name}` });
+		else if (!check(lang)) ctx.addIssue({ code: z.ZodIssueCode.custom, message });
+	});
 
 export const identifierSchema = z
 		.string()
@@ -24,21 +37,35 @@ export const functionArgsSchema = z
 	.array(z.object({ arg: identifierSchema, type: typeSchema }))
 	.min(1)
 	.max(8);
-export const solutionLangSchema = langSchema.refine(
-	(lang) => langs[lang].type == 'scripting' || langs[lang].type == 'compiled',
+export const solutionLangSchema = langSchema(
+	(lang) => lang.type == 'scripting' || lang.type == 'compiled',
 	'Language must be a programming language'
 );
-export const generatorLangSchema = langSchema.refine(
-	(lang) => langs[lang].type == 'scripting',
-	'Language must be a scripting language'
+export const generatorLangSchema = langSchema(
+	(lang) => lang.type == 'scripting' || lang.type == 'compiled',
+	'Language must be a programming language'
+);
+export const checkerLangSchema = langSchema(
+	(lang) => lang.checkers,
+	'Language does not support checkers'
 );
 
+/** How solutions' outputs are compared to the expected ones, mirrors `Comparison` in the backend */
+export const comparisonSchema = z.object({
+	absolute_epsilon: z.number().nonnegative().finite().default(0),
+	relative_epsilon: z.number().nonnegative().finite().default(0),
+	arrays: z.enum(['ordered', 'unordered', 'set', 'sorted']).default('ordered'),
+	ignore_case: z.boolean().default(false),
+	ignore_whitespace: z.boolean().default(false)
+});
+
 export const exerciseSchema = z.object({
 	title: z.string().trim().min(5).max(32),
 	description: longStringSchema.min(20).superRefine(sanitize_html),
 	function_name: identifierSchema,
 	args: functionArgsSchema,
 	output: typeSchema,
+	comparison: comparisonSchema.default({}),
 	solution: longStringSchema,
 	solution_lang: solutionLangSchema,
 
@@ -46,6 +73,8 @@ export const exerciseSchema = z.object({
 
 	generator_lang: generatorLangSchema.optional(),
 	generator: longStringSchema.optional(),
+	checker_lang: checkerLangSchema.optional(),
+	checker: longStringSchema.optional(),
 	generated_table: z.array(literalSchema).max(500)
 });
 
@@ -1,10 +1,11 @@
 import { request } from '$src/lib/request';
 import * as exec from '$src/routes/api/exec';
+import * as langs from '$src/routes/api/langs';
 import type { ValidationResult } from '$src/routes/api/schema';
 import { toast } from 'svelte-sonner';
 import { z, type ZodType } from 'zod';
 
-export { exec };
+export { exec, langs };
 
 export const api_post = async <Res>(
 	url: string,
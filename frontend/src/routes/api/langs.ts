@@ -0,0 +1,39 @@
+import { request } from '$src/lib/request';
+
+export type Lang = {
+	name: string;
+	type: 'scripting' | 'compiled' | 'config' | 'markup';
+	types: {
+		arrays: boolean;
+		variable_length_arrays: boolean;
+		complex_types: 'map'[];
+	};
+	extension: string;
+	editor_mode: string | null;
+	/** whether exercises can be graded by checkers in this language */
+	checkers: boolean;
+	/** markdown */
+	docs: string | null;
+	version: string | null;
+};
+
+let cached: Promise<Lang[]> | undefined;
+
+/** Every language the server knows about, fetched once */
+export const list = (): Promise<Lang[]> => {
+	cached ??= request
+		.get('/api/langs')
+		.then((res) => {
+			if (!res.ok) throw new Error(res.statusText);
+			return res.json();
+		})
+		.catch(() => {
+			cached = undefined;
+			return [];
+		});
+	return cached;
+};
+
+export const get = async (name: string): Promise<Lang | undefined> => {
+	return (await list()).find((lang) => lang.name == name);
+};
@@ -1,3 +1,4 @@
+import type { Lang } from '$src/routes/api/langs';
 import { z, ZodError } from 'zod';
 
 export const longStringSchema = z.string().max(6000);
@@ -6,18 +7,51 @@ export type ValidationResult<T> =
 	| { ok: false; error: ZodError | undefined; status: number | undefined }
 	| { ok: true; result: T };
 
+const scalar_types = ['bool', 'int', 'float', 'string'];
+
+export type Type =
+	| { kind: 'bool' | 'int' | 'float' | 'string' }
+	| { kind: 'array'; elem: Type; length: number | undefined }
+	| { kind: 'map'; value: Type };
+
+/** Mirrors `Type::from_str` in the backend, so `map<int[]>` and `int[2][]` parse too */
+export const parseType = (s: string): Type | undefined => {
+	s = s.trim();
+	const open_array = s.lastIndexOf('[');
+	if (s.endsWith(']') && open_array != -1) {
+		const elem = parseType(s.slice(0, open_array));
+		const length = s.slice(open_array + 1, -1).trim();
+		if (!elem) return undefined;
+		if (length == '') return { kind: 'array', elem, length: undefined };
+		// lengths are `u16`s
+		if (!/^\+?\d+$/.test(length) || Number(length) > 65535) return undefined;
+		return { kind: 'array', elem, length: Number(length) };
+	}
+	const open_complex = s.indexOf('<');
+	if (s.endsWith('>') && open_complex != -1) {
+		if (s.slice(0, open_complex).trim() != 'map') return undefined;
+		const value = parseType(s.slice(open_complex + 1, -1));
+		return value && { kind: 'map', value };
+	}
+	const scalar = scalar_types.find((t) => t == s) as 'bool' | 'int' | 'float' | 'string';
+	return scalar && { kind: scalar };
+};
+
 export const typeSchema = z
 	.string()
 	.min(1)
 	.max(30)
-	.regex(
-		/^(bool|int|float|string)(?:\[\])*$|^(\w+)<(bool|int|float|string)>$/,
-		'Invalid type syntax'
-	); // TODO: match capturing groups & dynamically validate with language config
+	.refine((s) => parseType(s) !== undefined, 'Invalid type syntax');
 
-export const valid_types = (lang: string | undefined) => {
-	// TODO: actual flexibility with language types?? who knows??
+/** Type suggestions for `lang`, or only the scalar types until it is known */
+export const valid_types = (lang: Lang | undefined) => {
+	if (!lang) return scalar_types;
+	const { arrays, variable_length_arrays, complex_types } = lang.types;
 	return [
-		'bool', 'int', 'float', 'string'
-	]
-};
\ No newline at end of file
+		...scalar_types,
+		...(variable_length_arrays ? scalar_types.map((t) => `${t}[]`) : []),
+		// fixed lengths are all some languages have, so suggest one to edit
+		...(arrays && !variable_length_arrays ? scalar_types.map((t) => `${t}[3]`) : []),
+		...complex_types.flatMap((c) => scalar_types.map((t) => `${c}<${t}>`))
+	];
+};
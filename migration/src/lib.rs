@@ -3,6 +3,14 @@ pub use sea_orm_migration::prelude::*;
 mod m20240221_034005_user;
 mod m20240223_151834_google_user;
 mod m20240306_021205_session;
+mod m20241216_204311_exercise;
+mod m20241218_172958_test_case;
+mod m20241220_134517_submission;
+mod m20241222_093140_submission_limits;
+mod m20241223_110452_submission_compile_error;
+mod m20241226_151203_exercise_comparison;
+mod m20241229_093512_exercise_checker;
+mod m20241229_094120_submission_checker_error;
 
 pub struct Migrator;
 
@@ -13,6 +21,14 @@ impl MigratorTrait for Migrator {
             Box::new(m20240221_034005_user::Migration),
             Box::new(m20240223_151834_google_user::Migration),
             Box::new(m20240306_021205_session::Migration),
+            Box::new(m20241216_204311_exercise::Migration),
+            Box::new(m20241218_172958_test_case::Migration),
+            Box::new(m20241220_134517_submission::Migration),
+            Box::new(m20241222_093140_submission_limits::Migration),
+            Box::new(m20241223_110452_submission_compile_error::Migration),
+            Box::new(m20241226_151203_exercise_comparison::Migration),
+            Box::new(m20241229_093512_exercise_checker::Migration),
+            Box::new(m20241229_094120_submission_checker_error::Migration),
         ]
     }
 }
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Exercise::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Exercise::ExerciseId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Exercise::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Exercise::Title).string().not_null())
+                    .col(ColumnDef::new(Exercise::Description).text().not_null())
+                    .col(ColumnDef::new(Exercise::FunctionName).string().not_null())
+                    .col(ColumnDef::new(Exercise::Args).json_binary().not_null())
+                    .col(ColumnDef::new(Exercise::Output).json_binary().not_null())
+                    .col(ColumnDef::new(Exercise::Solution).text().not_null())
+                    .col(ColumnDef::new(Exercise::SolutionLang).string().not_null())
+                    .col(ColumnDef::new(Exercise::StartingCode).text())
+                    .col(ColumnDef::new(Exercise::GeneratorLang).string())
+                    .col(ColumnDef::new(Exercise::Generator).text())
+                    .col(
+                        ColumnDef::new(Exercise::GeneratedTable)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Exercise::Created).timestamp().not_null())
+                    .col(ColumnDef::new(Exercise::Updated).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-exercise-user_id")
+                            .from(Exercise::Table, Exercise::UserId)
+                            .to(User::Table, User::UserId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-exercise-user_id")
+                    .table(Exercise::Table)
+                    .col(Exercise::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Exercise::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Exercise {
+    Table,
+    ExerciseId,
+    UserId,
+    Title,
+    Description,
+    FunctionName,
+    Args,
+    Output,
+    Solution,
+    SolutionLang,
+    StartingCode,
+    GeneratorLang,
+    Generator,
+    GeneratedTable,
+    Created,
+    Updated,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    UserId,
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TestCase::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TestCase::TestCaseId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TestCase::ExerciseId).uuid().not_null())
+                    .col(ColumnDef::new(TestCase::Position).integer().not_null())
+                    .col(ColumnDef::new(TestCase::Input).json_binary().not_null())
+                    .col(ColumnDef::new(TestCase::Output).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(TestCase::Hidden)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-test_case-exercise_id")
+                            .from(TestCase::Table, TestCase::ExerciseId)
+                            .to(Exercise::Table, Exercise::ExerciseId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-test_case-exercise_id-position")
+                    .table(TestCase::Table)
+                    .col(TestCase::ExerciseId)
+                    .col(TestCase::Position)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TestCase::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TestCase {
+    Table,
+    TestCaseId,
+    ExerciseId,
+    Position,
+    Input,
+    Output,
+    Hidden,
+}
+
+#[derive(DeriveIden)]
+enum Exercise {
+    Table,
+    ExerciseId,
+}
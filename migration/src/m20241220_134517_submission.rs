@@ -0,0 +1,113 @@
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Alias::new("submission_status"))
+                    .values([
+                        Alias::new("passed"),
+                        Alias::new("failed"),
+                        Alias::new("error"),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(Submission::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Submission::SubmissionId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Submission::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Submission::ExerciseId).uuid().not_null())
+                    .col(ColumnDef::new(Submission::Language).string().not_null())
+                    .col(ColumnDef::new(Submission::Code).text().not_null())
+                    .col(ColumnDef::new(Submission::Created).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(Submission::Status)
+                            .custom(Alias::new("submission_status"))
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Submission::Results).json_binary().not_null())
+                    .col(ColumnDef::new(Submission::Stdout).text().not_null())
+                    .col(ColumnDef::new(Submission::Stderr).text().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-submission-user_id")
+                            .from(Submission::Table, Submission::UserId)
+                            .to(User::Table, User::UserId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-submission-exercise_id")
+                            .from(Submission::Table, Submission::ExerciseId)
+                            .to(Exercise::Table, Exercise::ExerciseId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-submission-user_id-exercise_id")
+                    .table(Submission::Table)
+                    .col(Submission::UserId)
+                    .col(Submission::ExerciseId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Submission::Table).to_owned())
+            .await?;
+        manager
+            .drop_type(
+                Type::drop()
+                    .name(Alias::new("submission_status"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Submission {
+    Table,
+    SubmissionId,
+    UserId,
+    ExerciseId,
+    Language,
+    Code,
+    Created,
+    Status,
+    Results,
+    Stdout,
+    Stderr,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    UserId,
+}
+
+#[derive(DeriveIden)]
+enum Exercise {
+    Table,
+    ExerciseId,
+}
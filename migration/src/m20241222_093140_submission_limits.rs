@@ -0,0 +1,30 @@
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for value in [
+            "time_limit_exceeded",
+            "memory_limit_exceeded",
+            "output_limit_exceeded",
+        ] {
+            manager
+                .alter_type(
+                    Type::alter()
+                        .name(Alias::new("submission_status"))
+                        .add_value(Alias::new(value))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _: &SchemaManager) -> Result<(), DbErr> {
+        // postgres can not remove values from an enum, and leaving them is harmless
+        Ok(())
+    }
+}
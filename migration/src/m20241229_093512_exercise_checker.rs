@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Exercise::Table)
+                    .add_column(ColumnDef::new(Exercise::CheckerLang).string())
+                    .add_column(ColumnDef::new(Exercise::Checker).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Exercise::Table)
+                    .drop_column(Exercise::CheckerLang)
+                    .drop_column(Exercise::Checker)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Exercise {
+    Table,
+    CheckerLang,
+    Checker,
+}
@@ -1,6 +1,5 @@
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
-use docker_api::Docker;
 use handlebars::{Handlebars, Template, TemplateError};
 use sea_orm::DatabaseConnection;
 use serde::Serialize;
@@ -8,26 +7,78 @@ use serde::Serialize;
 use crate::{
     config::{Config, Secrets},
     langs::{LangInfo, Languages},
-    runner::RunnerRegistry,
+    literal::{LiteralHelper, TypeHelper},
+    runner::{check::CASES_FILE, exec::RESULTS_FILE, queue::ExecQueue, RunnerRegistry},
 };
 
 pub struct AppState {
     pub config: Config,
     pub secrets: Secrets,
     pub db: DatabaseConnection,
-    pub docker: Docker,
     pub runner_registry: RunnerRegistry,
+    pub queue: Arc<ExecQueue>,
     pub templates: Templates,
     pub langs: Languages,
 }
 
+/// A file in the work dir, as templates refer to it: by `name`, or by `stem` for
+/// languages that import modules or classes
+#[derive(Serialize)]
+struct TemplateFile<'a> {
+    name: &'a str,
+    stem: &'a str,
+}
+
+impl<'a> From<&'a str> for TemplateFile<'a> {
+    fn from(name: &'a str) -> Self {
+        let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+        Self { name, stem }
+    }
+}
+
+/// Every template gets these as `files`, so that it reads and writes the files
+/// the runner copies in and collects
+#[derive(Serialize)]
+struct TemplateFiles<'a> {
+    harness: TemplateFile<'a>,
+    generator: TemplateFile<'a>,
+    solution: TemplateFile<'a>,
+    checker: TemplateFile<'a>,
+    results: TemplateFile<'a>,
+    cases: TemplateFile<'a>,
+}
+
+impl<'a> TemplateFiles<'a> {
+    fn new(lang: &'a LangInfo) -> Self {
+        Self {
+            harness: lang.sources.harness.as_str().into(),
+            generator: lang.sources.generator.as_str().into(),
+            solution: lang.sources.solution.as_str().into(),
+            checker: lang.sources.checker.as_str().into(),
+            results: RESULTS_FILE.into(),
+            cases: CASES_FILE.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TemplateData<'a, T> {
+    #[serde(flatten)]
+    data: &'a T,
+    files: TemplateFiles<'a>,
+}
+
 pub struct Templates {
     handlebars: Handlebars<'static>,
+    literals: LiteralHelper,
 }
 
 impl Templates {
     pub fn new(handlebars: Handlebars<'static>) -> Self {
-        return Self { handlebars };
+        return Self {
+            handlebars,
+            literals: LiteralHelper::default(),
+        };
     }
 
     pub fn register_lang(&mut self, lang: &LangInfo) -> Result<(), TemplateError> {
@@ -36,6 +87,22 @@ impl Templates {
             .register_template_file(&format!("{name}/generator"), lang.generator_path())?;
         self.handlebars
             .register_template_file(&format!("{name}/runner"), lang.runner_path())?;
+        if lang.checkers {
+            self.handlebars
+                .register_template_file(&format!("{name}/checker"), lang.checker_path())?;
+        }
+        for partial in &lang.partials {
+            self.handlebars
+                .register_template_file(&format!("{name}/{partial}"), lang.partial_path(partial))?;
+        }
+
+        if let Some(style) = lang.literals {
+            self.literals.styles.insert(name.clone(), style);
+            self.handlebars
+                .register_helper("literal", Box::new(self.literals.clone()));
+            self.handlebars
+                .register_helper("type", Box::new(TypeHelper(self.literals.clone())));
+        }
 
         Ok(())
     }
@@ -49,6 +116,32 @@ impl Templates {
         T: Serialize,
     {
         let name = &lang.name;
-        self.handlebars.render(&format!("{name}/generator"), data)
+        let data = TemplateData {
+            data,
+            files: TemplateFiles::new(lang),
+        };
+        self.handlebars.render(&format!("{name}/generator"), &data)
+    }
+
+    pub fn render_runner<T>(
+        &self,
+        lang: &LangInfo,
+        data: &T,
+    ) -> Result<String, handlebars::RenderError>
+    where
+        T: Serialize,
+    {
+        let name = &lang.name;
+        let data = TemplateData {
+            data,
+            files: TemplateFiles::new(lang),
+        };
+        self.handlebars.render(&format!("{name}/runner"), &data)
+    }
+
+    pub fn render_checker(&self, lang: &LangInfo) -> Result<String, handlebars::RenderError> {
+        let name = &lang.name;
+        let data = serde_json::json!({ "files": TemplateFiles::new(lang) });
+        self.handlebars.render(&format!("{name}/checker"), &data)
     }
 }
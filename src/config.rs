@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
 
 use serde::{de::Error, Deserialize, Deserializer};
 
@@ -10,10 +10,168 @@ pub struct Config {
 
 #[derive(Deserialize)]
 pub struct DockerConfig {
+    /// where programs run; `host`, `image_label`, `name_prefix`, `pool` and
+    /// `reap_interval` only matter to docker
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// required by the bubblewrap backend
+    pub bubblewrap: Option<BubblewrapConfig>,
     pub host: String,
     pub image_label: String,
     pub name_prefix: String,
     pub container_name_prefix: String,
+    pub limits: Limits,
+    pub sandbox: SandboxConfig,
+    pub pool: PoolConfig,
+    /// how often to remove containers leaked by crashes; should be longer than any timeout
+    #[serde(deserialize_with = "parse_duration")]
+    pub reap_interval: Duration,
+    pub queue: QueueConfig,
+    /// compiled programs kept for every language, so running the same code again skips the build
+    pub compile_cache_size: usize,
+}
+
+/// See [`crate::runner::backend`]
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Docker,
+    Bubblewrap,
+}
+
+/// Runs programs in namespaces set up by `bwrap`, for hosts without a Docker daemon
+#[derive(Deserialize, Debug, Clone)]
+pub struct BubblewrapConfig {
+    #[serde(default = "default_bwrap")]
+    pub bwrap: PathBuf,
+    /// a cgroup v2 directory the server may create child groups in, with the
+    /// `cpu`, `memory` and `pids` controllers enabled
+    pub cgroup: PathBuf,
+    /// holds a root filesystem for every language, named like its directory in
+    /// `languages`. The host's `/usr`, `/etc` and friends are used if unset.
+    pub root: Option<PathBuf>,
+    /// the whole environment of sandboxed programs, since the server's own may have secrets
+    #[serde(default = "default_env")]
+    pub env: BTreeMap<String, String>,
+}
+
+fn default_bwrap() -> PathBuf {
+    "bwrap".into()
+}
+
+fn default_env() -> BTreeMap<String, String> {
+    BTreeMap::from([(
+        "PATH".to_string(),
+        "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin".to_string(),
+    )])
+}
+
+/// How many containers may exist at once, see [`crate::runner::queue`]
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueueConfig {
+    pub max_running: usize,
+    /// jobs waiting for one of the `max_running` slots
+    pub max_queued: usize,
+    /// jobs a single user may have running or waiting
+    pub max_per_user: usize,
+    /// sent as `Retry-After` when a job is turned away
+    #[serde(deserialize_with = "parse_duration")]
+    pub retry_after: Duration,
+}
+
+/// Containers kept ready for every language, see [`crate::runner::pool`]
+#[derive(Deserialize, Debug, Clone)]
+pub struct PoolConfig {
+    /// `0` disables the pool
+    pub max_idle: usize,
+    #[serde(deserialize_with = "parse_duration")]
+    pub health_check_interval: Duration,
+}
+
+/// Hardening applied to every runner container on top of its [`Limits`]
+#[derive(Deserialize, Debug, Clone)]
+pub struct SandboxConfig {
+    /// containers always run as this user, whatever the Dockerfile says; must not be root
+    pub uid: u32,
+    pub gid: u32,
+    /// size of the writable tmpfs mounted at `/tmp`
+    #[serde(deserialize_with = "parse_bytes")]
+    pub tmpfs_size: u64,
+    /// largest file a program may write
+    #[serde(deserialize_with = "parse_bytes")]
+    pub file_size: u64,
+    pub open_files: u64,
+}
+
+/// Resources a single container may use before it is killed
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Limits {
+    pub cpus: f64,
+    #[serde(deserialize_with = "parse_bytes")]
+    pub memory: u64,
+    pub pids: u64,
+    /// wall-clock time, including interpreter startup
+    #[serde(deserialize_with = "parse_duration")]
+    pub timeout: Duration,
+    /// combined size of stdout and stderr
+    #[serde(deserialize_with = "parse_bytes")]
+    pub output: u64,
+}
+
+/// Partial [`Limits`], as set in a language's `config.json` or an exec request
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LimitOverrides {
+    pub cpus: Option<f64>,
+    #[serde(default, deserialize_with = "parse_optional_bytes")]
+    pub memory: Option<u64>,
+    pub pids: Option<u64>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    pub timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "parse_optional_bytes")]
+    pub output: Option<u64>,
+}
+
+impl LimitOverrides {
+    /// Check that every limit that is set leaves room to run anything
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.cpus.is_some_and(|c| !c.is_finite() || c <= 0.0) {
+            return Err("`cpus` must be positive");
+        }
+        let zero = [self.memory, self.pids, self.output]
+            .into_iter()
+            .any(|limit| limit == Some(0));
+        if zero || self.timeout.is_some_and(|t| t.is_zero()) {
+            return Err("Limits must be positive");
+        }
+        Ok(())
+    }
+}
+
+impl Limits {
+    /// Replace every limit that is set in `overrides`
+    pub fn apply(&self, overrides: &LimitOverrides) -> Self {
+        Self {
+            cpus: overrides.cpus.unwrap_or(self.cpus),
+            memory: overrides.memory.unwrap_or(self.memory),
+            pids: overrides.pids.unwrap_or(self.pids),
+            timeout: overrides.timeout.unwrap_or(self.timeout),
+            output: overrides.output.unwrap_or(self.output),
+        }
+    }
+
+    /// Like [`Limits::apply`], but overrides may only lower limits
+    pub fn tighten(&self, overrides: &LimitOverrides) -> Self {
+        Self {
+            cpus: overrides.cpus.map_or(self.cpus, |c| c.min(self.cpus)),
+            memory: overrides.memory.map_or(self.memory, |m| m.min(self.memory)),
+            pids: overrides.pids.map_or(self.pids, |p| p.min(self.pids)),
+            timeout: overrides
+                .timeout
+                .map_or(self.timeout, |t| t.min(self.timeout)),
+            output: overrides.output.map_or(self.output, |o| o.min(self.output)),
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -53,6 +211,7 @@ where
                 .parse()
                 .map_err(|_| D::Error::custom("Could not parse number in duration chunk"))?;
             let duration = match unit {
+                "ms" => Duration::from_millis(num),
                 "s" => Duration::from_secs(num),
                 "m" => Duration::from_secs(num * 60),
                 "h" => Duration::from_secs(num * 60 * 60),
@@ -62,7 +221,7 @@ where
                 "y" => Duration::from_secs(num * 60 * 60 * 24 * 365),
                 _ => {
                     return Err(D::Error::custom(format!(
-                    "Unrecognized unit in duration chunk: {}; Valid units are s, m, h, d, w, M, y",
+                    "Unrecognized unit in duration chunk: {}; Valid units are ms, s, m, h, d, w, M, y",
                     unit
                 )))
                 }
@@ -73,3 +232,47 @@ where
         .into_iter()
         .sum())
 }
+
+/// Sizes like `16m` or `512k`, in powers of 1024
+fn parse_bytes<'de, D>(de: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(de)?;
+    let s = s.trim();
+    let (num, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let num: u64 = num
+        .parse()
+        .map_err(|_| D::Error::custom("Could not parse number in byte size"))?;
+    let shift = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" => 10,
+        "m" | "mb" => 20,
+        "g" | "gb" => 30,
+        unit => {
+            return Err(D::Error::custom(format!(
+                "Unrecognized unit in byte size: {unit}; Valid units are b, k, m, g"
+            )))
+        }
+    };
+    num.checked_mul(1 << shift)
+        .ok_or(D::Error::custom("Byte size is too large"))
+}
+
+fn parse_optional_bytes<'de, D>(de: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Bytes(#[serde(deserialize_with = "parse_bytes")] u64);
+    Ok(Option::<Bytes>::deserialize(de)?.map(|b| b.0))
+}
+
+fn parse_optional_duration<'de, D>(de: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Time(#[serde(deserialize_with = "parse_duration")] Duration);
+    Ok(Option::<Time>::deserialize(de)?.map(|d| d.0))
+}
@@ -4,7 +4,9 @@ use std::{
 };
 
 use eyre::ensure;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::LimitOverrides, literal::LiteralStyle, routes::exec::Type};
 
 #[derive(Debug)]
 pub struct Languages {
@@ -16,14 +18,12 @@ impl Languages {
         Ok(Self {
             langs: fs::read_dir("languages")?
                 .filter_map(|entry| -> Option<LangInfo> {
-                    let entry = entry.ok()?;
-                    let path = entry.path();
-                    let filename = entry.file_name();
+                    let path = entry.ok()?.path();
                     if path.is_file() {
                         return None;
                     }
 
-                    LangInfo::new(filename.to_str()?).ok()
+                    LangInfo::new(&path).ok()
                 })
                 .collect(),
         })
@@ -38,7 +38,7 @@ impl std::ops::Deref for Languages {
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum LangType {
     Scripting,
@@ -47,22 +47,126 @@ pub enum LangType {
     Markup,
 }
 
+impl LangType {
+    /// Whether programs in this language can be run by a [`crate::runner::Runner`]
+    pub fn is_runnable(self) -> bool {
+        matches!(self, LangType::Scripting | LangType::Compiled)
+    }
+}
+
+/// Types made of other types, other than arrays
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComplexType {
+    Map,
+}
+
+/// How a compiled language turns its sources into something runnable
+#[derive(Debug, Clone, Deserialize)]
+pub struct BuildConfig {
+    /// Runs in the work dir, with the harness and the user's code in place
+    pub command: Vec<String>,
+    /// The single file `command` produces, relative to the work dir. It is the
+    /// only file copied into the container that runs the program.
+    pub artifact: String,
+    /// Replaces the language's limits while compiling
+    #[serde(default)]
+    pub limits: LimitOverrides,
+}
+
+/// What the harness and the user's code are called in the work dir. Each one
+/// defaults to `main`, `gen`, `solution` or `checker`, with the language's
+/// extension.
+/// Templates get them as `{{files.solution.name}}`, or `{{files.solution.stem}}`
+/// for module and class names, but the build command and entrypoint have to
+/// name the harness themselves.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SourceFiles {
+    #[serde(default)]
+    pub harness: String,
+    #[serde(default)]
+    pub generator: String,
+    #[serde(default)]
+    pub solution: String,
+    #[serde(default)]
+    pub checker: String,
+}
+
+fn default_work_dir() -> String {
+    "/runner".to_string()
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct LangInfo {
+    /// The name of the language's directory
     #[serde(default)]
     pub name: String,
+    #[serde(skip)]
+    pub dir: PathBuf,
     pub r#type: LangType,
+    pub extension: String,
+    /// Which of the frontend's editor languages highlights this one
+    #[serde(default)]
+    pub editor_mode: Option<String>,
+    /// The version of the language that programs run with, for display
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Contents of the optional `docs.md`, explaining how to write generators and
+    /// solutions in this language
+    #[serde(skip)]
+    pub docs: Option<String>,
+    /// Where sources are copied to and programs run, which the image has to
+    /// make writable for the sandbox user
+    #[serde(default = "default_work_dir")]
+    pub work_dir: String,
+    #[serde(default)]
+    pub sources: SourceFiles,
+    /// Runs the program, or the artifact of compiled languages, in the work
+    /// dir. The image's entrypoint is used if this is not set.
+    #[serde(default)]
+    pub entrypoint: Option<Vec<String>>,
+    /// Required for compiled languages
+    #[serde(default)]
+    pub build: Option<BuildConfig>,
+    /// How to write values as source code literals and name their types, if the
+    /// language supports it
+    #[serde(default)]
+    pub literals: Option<LiteralStyle>,
+    /// Whether arrays may leave out their length, like `int[]`
+    #[serde(default)]
+    pub variable_length_arrays: bool,
+    #[serde(default)]
+    pub complex_types: Vec<ComplexType>,
+    /// Replaces the limits from `config.yaml` for this language
+    #[serde(default)]
+    pub limits: LimitOverrides,
+    /// Replaces `pool.max_idle` from `config.yaml` for this language
+    #[serde(default)]
+    pub pool_size: Option<usize>,
+    /// Contents of the optional `seccomp.json` profile, replacing Docker's default
+    #[serde(skip)]
+    pub seccomp: Option<String>,
+    /// Whether there is a `checker.hbs`, so that exercises can be graded by
+    /// checkers written in this language
+    #[serde(skip)]
+    pub checkers: bool,
+    /// Names of the other `.hbs` files, which the generator and runner can
+    /// include as `{{> {lang}/{partial}}}`
+    #[serde(skip)]
+    pub partials: Vec<String>,
 }
 
 impl LangInfo {
-    pub fn new(name: &str) -> Result<Self, eyre::Error> {
-        let dir_string = format!("languages/{name}");
-        let dir = Path::new(&dir_string);
-
+    /// Load the language defined in `dir`, named after the directory
+    pub fn new(dir: &Path) -> Result<Self, eyre::Error> {
         ensure!(
             dir.is_dir(),
-            format!("Expected directory `{dir_string}` to exist")
+            format!("Expected directory `{}` to exist", dir.display())
         );
+        let name = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| eyre::eyre!("Invalid language directory `{}`", dir.display()))?;
         ensure!(dir.join("Dockerfile").is_file());
         ensure!(dir.join("generator.hbs").is_file());
         ensure!(dir.join("runner.hbs").is_file());
@@ -74,22 +178,128 @@ impl LangInfo {
         let mut config: LangInfo = serde_json::from_str(&config)?;
 
         config.name = name.into();
+        config.dir = dir.to_path_buf();
+        for (file, default) in [
+            (&mut config.sources.harness, "main"),
+            (&mut config.sources.generator, "gen"),
+            (&mut config.sources.solution, "solution"),
+            (&mut config.sources.checker, "checker"),
+        ] {
+            if file.is_empty() {
+                *file = format!("{default}.{}", config.extension);
+            }
+        }
+        ensure!(
+            (config.r#type == LangType::Compiled) == config.build.is_some(),
+            "Compiled languages, and only compiled languages, need a `build` config"
+        );
+
+        config.checkers = config.checker_path().is_file();
+
+        let docs = dir.join("docs.md");
+        if docs.is_file() {
+            config.docs = Some(fs::read_to_string(docs)?);
+        }
+
+        let seccomp = dir.join("seccomp.json");
+        if seccomp.is_file() {
+            // docker expects the profile itself rather than a path, on a single line
+            let profile: serde_json::Value = serde_json::from_str(&fs::read_to_string(seccomp)?)?;
+            config.seccomp = Some(profile.to_string());
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let template = path.extension().is_some_and(|ext| ext == "hbs");
+            if template && !["generator", "runner", "checker"].contains(&stem) {
+                config.partials.push(stem.to_string());
+            }
+        }
 
         Ok(config)
     }
 
     pub fn dockerfile_path(&self) -> PathBuf {
-        let name = &self.name;
-        return format!("languages/{name}/Dockerfile").into();
+        return self.dir.join("Dockerfile");
     }
 
     pub fn generator_path(&self) -> PathBuf {
-        let name = &self.name;
-        return format!("languages/{name}/generator.hbs").into();
+        return self.dir.join("generator.hbs");
     }
 
     pub fn runner_path(&self) -> PathBuf {
-        let name = &self.name;
-        return format!("languages/{name}/runner.hbs").into();
+        return self.dir.join("runner.hbs");
+    }
+
+    pub fn checker_path(&self) -> PathBuf {
+        return self.dir.join("checker.hbs");
+    }
+
+    pub fn partial_path(&self, partial: &str) -> PathBuf {
+        return self.dir.join(format!("{partial}.hbs"));
+    }
+
+    /// The innermost part of `ty` that this language can not represent, if any
+    pub fn unsupported<'a>(&self, ty: &'a Type) -> Option<&'a Type> {
+        if let Type::Array(inner, _) | Type::Map(inner) = ty {
+            if let Some(unsupported) = self.unsupported(inner) {
+                return Some(unsupported);
+            }
+        }
+
+        let supported = match ty {
+            Type::Array(_, None) => self.variable_length_arrays,
+            Type::Map(_) => self.complex_types.contains(&ComplexType::Map),
+            _ => true,
+        };
+        // templates of typed languages also need to name it
+        let named = self
+            .literals
+            .is_none_or(|style| style.renderer().type_name(ty).is_ok());
+        (!supported || !named).then_some(ty)
+    }
+
+    /// The absolute path of `file` in the work dir
+    pub fn work_path(&self, file: &str) -> String {
+        format!("{}/{file}", self.work_dir.trim_end_matches('/'))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load_languages() {
+        for entry in fs::read_dir("languages").unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(e) = LangInfo::new(&path) {
+                panic!("Could not load `{}`: {e:?}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let lang: LangInfo = serde_json::from_value(serde_json::json!({
+            "type": "compiled",
+            "extension": "c",
+            "literals": "c",
+        }))
+        .unwrap();
+        let ty = |s: &str| s.parse::<Type>().unwrap();
+
+        assert_eq!(lang.unsupported(&ty("string")), None);
+        assert_eq!(lang.unsupported(&ty("int[3]")), Some(&ty("int[3]")));
+
+        let lang = LangInfo {
+            literals: None,
+            ..lang
+        };
+        assert_eq!(lang.unsupported(&ty("int[3]")), None);
+        assert_eq!(lang.unsupported(&ty("map<int[]>[2]")), Some(&ty("int[]")));
     }
 }
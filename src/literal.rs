@@ -0,0 +1,634 @@
+use std::collections::BTreeMap;
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::routes::exec::Type;
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum LiteralError {
+    #[error("Expected a value of type `{expected}`, got `{actual}`")]
+    Mismatch { expected: Type, actual: Value },
+    #[error("Type `{0}` has no literal syntax in this language")]
+    Unsupported(Type),
+}
+
+/// Turns JSON values into source code literals for some language, so that
+/// templates can call a function with test case values directly
+pub trait LiteralRenderer: Send + Sync {
+    /// The language's own name for `ty`, for templates of typed languages to
+    /// declare arguments and results with
+    fn type_name(&self, ty: &Type) -> Result<String, LiteralError>;
+
+    fn bool(&self, b: bool) -> String;
+
+    fn int(&self, n: &serde_json::Number) -> String {
+        n.to_string()
+    }
+
+    fn float(&self, f: f64) -> String {
+        format!("{f:?}")
+    }
+
+    fn string(&self, s: &str) -> String;
+
+    /// `values` have already been rendered
+    fn array(
+        &self,
+        elem: &Type,
+        len: Option<u16>,
+        values: Vec<String>,
+    ) -> Result<String, LiteralError>;
+
+    /// `entries` have already been rendered
+    fn map(&self, value: &Type, entries: Vec<(String, String)>) -> Result<String, LiteralError>;
+
+    fn render(&self, ty: &Type, value: &Value) -> Result<String, LiteralError> {
+        let mismatch = || LiteralError::Mismatch {
+            expected: ty.clone(),
+            actual: value.clone(),
+        };
+        if !ty.check(value) {
+            return Err(mismatch());
+        }
+
+        match (ty, value) {
+            (Type::Bool, Value::Bool(b)) => Ok(self.bool(*b)),
+            (Type::Int, Value::Number(n)) => Ok(self.int(n)),
+            (Type::Float, Value::Number(n)) => Ok(self.float(n.as_f64().ok_or_else(mismatch)?)),
+            (Type::String, Value::String(s)) => Ok(self.string(s)),
+            (Type::Array(elem, len), Value::Array(values)) => {
+                let values = values
+                    .iter()
+                    .map(|v| self.render(elem, v))
+                    .collect::<Result<_, _>>()?;
+                self.array(elem, *len, values)
+            }
+            (Type::Map(ty), Value::Object(map)) => {
+                let entries = map
+                    .iter()
+                    .map(|(k, v)| Ok((self.string(k), self.render(ty, v)?)))
+                    .collect::<Result<_, _>>()?;
+                self.map(ty, entries)
+            }
+            _ => Err(mismatch()),
+        }
+    }
+}
+
+/// Which [`LiteralRenderer`] a language uses, set by `literals` in its `config.json`
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LiteralStyle {
+    Python,
+    JavaScript,
+    Rust,
+    Java,
+    C,
+    Cpp,
+}
+
+impl LiteralStyle {
+    pub fn renderer(self) -> &'static dyn LiteralRenderer {
+        match self {
+            LiteralStyle::Python => &Python,
+            LiteralStyle::JavaScript => &JavaScript,
+            LiteralStyle::Rust => &Rust,
+            LiteralStyle::Java => &Java,
+            LiteralStyle::C => &C,
+            LiteralStyle::Cpp => &Cpp,
+        }
+    }
+}
+
+/// Escape a string the way most C-like languages expect, with `unicode`
+/// formatting any control characters that have no short escape
+fn escape(s: &str, unicode: fn(u32) -> String) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&unicode(c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Escape a string for C and C++, which can't write control characters as
+/// `\u` escapes. Their UTF-8 bytes are written as octal escapes instead,
+/// which unlike `\x` escapes stop after 3 digits.
+fn escape_octal(s: &str) -> String {
+    escape(s, |c| {
+        let c = char::from_u32(c).unwrap_or_default();
+        let mut buf = [0; 4];
+        c.encode_utf8(&mut buf)
+            .bytes()
+            .map(|b| format!("\\{b:03o}"))
+            .collect()
+    })
+}
+
+fn join(values: Vec<String>) -> String {
+    values.join(", ")
+}
+
+fn join_entries(entries: Vec<(String, String)>, sep: &str) -> String {
+    entries
+        .into_iter()
+        .map(|(k, v)| format!("{k}{sep}{v}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub struct Python;
+
+impl LiteralRenderer for Python {
+    fn type_name(&self, ty: &Type) -> Result<String, LiteralError> {
+        Ok(match ty {
+            Type::Bool => "bool".to_string(),
+            Type::Int => "int".to_string(),
+            Type::Float => "float".to_string(),
+            Type::String => "str".to_string(),
+            Type::Array(elem, _) => format!("list[{}]", self.type_name(elem)?),
+            Type::Map(value) => format!("dict[str, {}]", self.type_name(value)?),
+        })
+    }
+
+    fn bool(&self, b: bool) -> String {
+        match b {
+            true => "True".to_string(),
+            false => "False".to_string(),
+        }
+    }
+
+    fn string(&self, s: &str) -> String {
+        escape(s, |c| format!("\\U{c:08x}"))
+    }
+
+    fn array(&self, _: &Type, _: Option<u16>, values: Vec<String>) -> Result<String, LiteralError> {
+        Ok(format!("[{}]", join(values)))
+    }
+
+    fn map(&self, _: &Type, entries: Vec<(String, String)>) -> Result<String, LiteralError> {
+        Ok(format!("{{{}}}", join_entries(entries, ": ")))
+    }
+}
+
+pub struct JavaScript;
+
+impl LiteralRenderer for JavaScript {
+    /// As a TypeScript annotation
+    fn type_name(&self, ty: &Type) -> Result<String, LiteralError> {
+        Ok(match ty {
+            Type::Bool => "boolean".to_string(),
+            Type::Int | Type::Float => "number".to_string(),
+            Type::String => "string".to_string(),
+            Type::Array(elem, _) => format!("{}[]", self.type_name(elem)?),
+            Type::Map(value) => format!("Record<string, {}>", self.type_name(value)?),
+        })
+    }
+
+    fn bool(&self, b: bool) -> String {
+        b.to_string()
+    }
+
+    fn string(&self, s: &str) -> String {
+        escape(s, |c| format!("\\u{{{c:x}}}"))
+    }
+
+    fn array(&self, _: &Type, _: Option<u16>, values: Vec<String>) -> Result<String, LiteralError> {
+        Ok(format!("[{}]", join(values)))
+    }
+
+    fn map(&self, _: &Type, entries: Vec<(String, String)>) -> Result<String, LiteralError> {
+        Ok(format!("{{{}}}", join_entries(entries, ": ")))
+    }
+}
+
+pub struct Rust;
+
+impl LiteralRenderer for Rust {
+    fn type_name(&self, ty: &Type) -> Result<String, LiteralError> {
+        Ok(match ty {
+            Type::Bool => "bool".to_string(),
+            Type::Int => "i64".to_string(),
+            Type::Float => "f64".to_string(),
+            Type::String => "String".to_string(),
+            Type::Array(elem, None) => format!("Vec<{}>", self.type_name(elem)?),
+            Type::Array(elem, Some(len)) => format!("[{}; {len}]", self.type_name(elem)?),
+            Type::Map(value) => format!(
+                "std::collections::HashMap<String, {}>",
+                self.type_name(value)?
+            ),
+        })
+    }
+
+    fn bool(&self, b: bool) -> String {
+        b.to_string()
+    }
+
+    fn string(&self, s: &str) -> String {
+        format!("{}.to_string()", escape(s, |c| format!("\\u{{{c:x}}}")))
+    }
+
+    fn array(
+        &self,
+        _: &Type,
+        len: Option<u16>,
+        values: Vec<String>,
+    ) -> Result<String, LiteralError> {
+        match len {
+            Some(_) => Ok(format!("[{}]", join(values))),
+            None => Ok(format!("vec![{}]", join(values))),
+        }
+    }
+
+    fn map(&self, _: &Type, entries: Vec<(String, String)>) -> Result<String, LiteralError> {
+        let entries = entries
+            .into_iter()
+            .map(|(k, v)| format!("({k}, {v})"))
+            .collect();
+        Ok(format!(
+            "std::collections::HashMap::from([{}])",
+            join(entries)
+        ))
+    }
+}
+
+pub struct Java;
+
+impl Java {
+    fn java_type(ty: &Type, boxed: bool) -> String {
+        match (ty, boxed) {
+            (Type::Bool, false) => "boolean".to_string(),
+            (Type::Bool, true) => "Boolean".to_string(),
+            (Type::Int, false) => "long".to_string(),
+            (Type::Int, true) => "Long".to_string(),
+            (Type::Float, false) => "double".to_string(),
+            (Type::Float, true) => "Double".to_string(),
+            (Type::String, _) => "String".to_string(),
+            (Type::Array(elem, _), _) => format!("{}[]", Self::java_type(elem, false)),
+            (Type::Map(value), _) => {
+                format!("java.util.Map<String, {}>", Self::java_type(value, true))
+            }
+        }
+    }
+}
+
+impl LiteralRenderer for Java {
+    fn type_name(&self, ty: &Type) -> Result<String, LiteralError> {
+        match ty {
+            // java does not allow creating arrays of generic types
+            Type::Array(elem, _) if matches!(**elem, Type::Map(_)) => {
+                Err(LiteralError::Unsupported(ty.clone()))
+            }
+            Type::Array(inner, _) | Type::Map(inner) => {
+                self.type_name(inner).map(|_| Self::java_type(ty, false))
+            }
+            _ => Ok(Self::java_type(ty, false)),
+        }
+    }
+
+    fn bool(&self, b: bool) -> String {
+        b.to_string()
+    }
+
+    fn int(&self, n: &serde_json::Number) -> String {
+        format!("{n}L")
+    }
+
+    fn string(&self, s: &str) -> String {
+        escape(s, |c| format!("\\u{c:04x}"))
+    }
+
+    fn array(
+        &self,
+        elem: &Type,
+        _: Option<u16>,
+        values: Vec<String>,
+    ) -> Result<String, LiteralError> {
+        // java does not allow creating arrays of generic types
+        if let Type::Map(_) = elem {
+            return Err(LiteralError::Unsupported(Type::Array(
+                Box::new(elem.clone()),
+                None,
+            )));
+        }
+        Ok(format!(
+            "new {}[]{{{}}}",
+            Self::java_type(elem, false),
+            join(values)
+        ))
+    }
+
+    fn map(&self, _: &Type, entries: Vec<(String, String)>) -> Result<String, LiteralError> {
+        let entries = entries
+            .into_iter()
+            .map(|(k, v)| format!("java.util.Map.entry({k}, {v})"))
+            .collect();
+        Ok(format!("java.util.Map.ofEntries({})", join(entries)))
+    }
+}
+
+/// Strings are `const char *`, and there are no arrays or maps, since C has no
+/// way of passing them around without their lengths
+pub struct C;
+
+impl LiteralRenderer for C {
+    fn type_name(&self, ty: &Type) -> Result<String, LiteralError> {
+        match ty {
+            Type::Bool => Ok("bool".to_string()),
+            Type::Int => Ok("long long".to_string()),
+            Type::Float => Ok("double".to_string()),
+            Type::String => Ok("const char *".to_string()),
+            Type::Array(..) | Type::Map(_) => Err(LiteralError::Unsupported(ty.clone())),
+        }
+    }
+
+    fn bool(&self, b: bool) -> String {
+        b.to_string()
+    }
+
+    fn int(&self, n: &serde_json::Number) -> String {
+        format!("{n}LL")
+    }
+
+    fn string(&self, s: &str) -> String {
+        escape_octal(s)
+    }
+
+    fn array(&self, elem: &Type, len: Option<u16>, _: Vec<String>) -> Result<String, LiteralError> {
+        Err(LiteralError::Unsupported(Type::Array(
+            Box::new(elem.clone()),
+            len,
+        )))
+    }
+
+    fn map(&self, value: &Type, _: Vec<(String, String)>) -> Result<String, LiteralError> {
+        Err(LiteralError::Unsupported(Type::Map(Box::new(
+            value.clone(),
+        ))))
+    }
+}
+
+pub struct Cpp;
+
+impl LiteralRenderer for Cpp {
+    fn type_name(&self, ty: &Type) -> Result<String, LiteralError> {
+        Ok(match ty {
+            Type::Bool => "bool".to_string(),
+            Type::Int => "long long".to_string(),
+            Type::Float => "double".to_string(),
+            Type::String => "std::string".to_string(),
+            Type::Array(elem, None) => format!("std::vector<{}>", self.type_name(elem)?),
+            Type::Array(elem, Some(len)) => {
+                format!("std::array<{}, {len}>", self.type_name(elem)?)
+            }
+            Type::Map(value) => format!("std::map<std::string, {}>", self.type_name(value)?),
+        })
+    }
+
+    fn bool(&self, b: bool) -> String {
+        b.to_string()
+    }
+
+    fn int(&self, n: &serde_json::Number) -> String {
+        format!("{n}LL")
+    }
+
+    /// With an explicit length, since the string may contain `\0`
+    fn string(&self, s: &str) -> String {
+        format!("std::string({}, {})", escape_octal(s), s.len())
+    }
+
+    fn array(
+        &self,
+        elem: &Type,
+        len: Option<u16>,
+        values: Vec<String>,
+    ) -> Result<String, LiteralError> {
+        let ty = self.type_name(&Type::Array(Box::new(elem.clone()), len))?;
+        Ok(format!("{ty}{{{}}}", join(values)))
+    }
+
+    fn map(&self, value: &Type, entries: Vec<(String, String)>) -> Result<String, LiteralError> {
+        let ty = self.type_name(&Type::Map(Box::new(value.clone())))?;
+        let entries = entries
+            .into_iter()
+            .map(|(k, v)| format!("{{{k}, {v}}}"))
+            .collect();
+        Ok(format!("{ty}{{{}}}", join(entries)))
+    }
+}
+
+/// `{{literal value type}}`: render `value` as a literal of the language whose
+/// template is being rendered
+#[derive(Clone, Default)]
+pub struct LiteralHelper {
+    pub styles: BTreeMap<String, LiteralStyle>,
+}
+
+impl LiteralHelper {
+    /// The renderer of the language whose template is being rendered
+    fn renderer(&self, rc: &RenderContext) -> Result<&'static dyn LiteralRenderer, RenderError> {
+        // templates are registered as `{lang}/{template}`
+        let lang = rc
+            .get_root_template_name()
+            .and_then(|name| name.split_once('/'))
+            .map(|(lang, _)| lang)
+            .unwrap_or_default();
+        let style = self.styles.get(lang).ok_or_else(|| {
+            RenderErrorReason::Other(format!("Language `{lang}` has no literal style"))
+        })?;
+        Ok(style.renderer())
+    }
+}
+
+fn type_param(h: &Helper, helper: &'static str, index: usize) -> Result<Type, RenderError> {
+    h.param(index)
+        .and_then(|p| p.value().as_str())
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))?
+        .parse()
+        .map_err(|e: crate::routes::exec::ParseTypeError| {
+            RenderErrorReason::Other(e.to_string()).into()
+        })
+}
+
+impl HelperDef for LiteralHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("literal", 0))?
+            .value();
+        let ty = type_param(h, "literal", 1)?;
+
+        let literal = self
+            .renderer(rc)?
+            .render(&ty, value)
+            .map_err(|e| RenderErrorReason::Other(e.to_string()))?;
+        out.write(&literal)?;
+        Ok(())
+    }
+}
+
+/// `{{type type}}`: write the name the language whose template is being
+/// rendered has for `type`
+#[derive(Clone)]
+pub struct TypeHelper(pub LiteralHelper);
+
+impl HelperDef for TypeHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let ty = type_param(h, "type", 0)?;
+
+        let name = self
+            .0
+            .renderer(rc)?
+            .type_name(&ty)
+            .map_err(|e| RenderErrorReason::Other(e.to_string()))?;
+        out.write(&name)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(style: LiteralStyle, ty: &str, value: Value) -> Result<String, LiteralError> {
+        style.renderer().render(&ty.parse().unwrap(), &value)
+    }
+
+    #[test]
+    fn test_python() {
+        let py = LiteralStyle::Python;
+        assert_eq!(render(py, "bool", json!(true)).unwrap(), "True");
+        assert_eq!(render(py, "float", json!(1)).unwrap(), "1.0");
+        assert_eq!(
+            render(py, "string", json!("a\"b\n")).unwrap(),
+            r#""a\"b\n""#
+        );
+        assert_eq!(
+            render(py, "map<int[]>", json!({ "a": [1, 2] })).unwrap(),
+            r#"{"a": [1, 2]}"#
+        );
+    }
+
+    #[test]
+    fn test_rust() {
+        let rs = LiteralStyle::Rust;
+        assert_eq!(
+            render(rs, "int[][2]", json!([[1], []])).unwrap(),
+            "[vec![1], vec![]]"
+        );
+        assert_eq!(
+            render(rs, "map<string>", json!({ "k": "v" })).unwrap(),
+            r#"std::collections::HashMap::from([("k".to_string(), "v".to_string())])"#
+        );
+    }
+
+    #[test]
+    fn test_java() {
+        let java = LiteralStyle::Java;
+        assert_eq!(
+            render(java, "int[][]", json!([[1, 2]])).unwrap(),
+            "new long[][]{new long[]{1L, 2L}}"
+        );
+        assert_eq!(
+            render(java, "string", json!("\u{1}")).unwrap(),
+            r#""\u0001""#
+        );
+        assert!(matches!(
+            render(java, "map<int>[]", json!([{}])),
+            Err(LiteralError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_c() {
+        let c = LiteralStyle::C;
+        assert_eq!(render(c, "int", json!(-3)).unwrap(), "-3LL");
+        assert_eq!(
+            render(c, "string", json!("a\u{1}1")).unwrap(),
+            r#""a\0011""#
+        );
+        assert!(matches!(
+            render(c, "int[3]", json!([1, 2, 3])),
+            Err(LiteralError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_cpp() {
+        let cpp = LiteralStyle::Cpp;
+        assert_eq!(
+            render(cpp, "int[2][]", json!([[1, 2]])).unwrap(),
+            "std::vector<std::array<long long, 2>>{std::array<long long, 2>{1LL, 2LL}}"
+        );
+        assert_eq!(
+            render(cpp, "map<string>", json!({ "k": "\u{85}" })).unwrap(),
+            r#"std::map<std::string, std::string>{{std::string("k", 1), std::string("\302\205", 2)}}"#
+        );
+    }
+
+    #[test]
+    fn test_type_name() {
+        let name = |style: LiteralStyle, ty: &str| style.renderer().type_name(&ty.parse().unwrap());
+        let ty = "map<float[3]>[]";
+        assert_eq!(
+            name(LiteralStyle::Python, ty).unwrap(),
+            "list[dict[str, list[float]]]"
+        );
+        assert_eq!(
+            name(LiteralStyle::JavaScript, ty).unwrap(),
+            "Record<string, number[]>[]"
+        );
+        assert_eq!(
+            name(LiteralStyle::Rust, ty).unwrap(),
+            "Vec<std::collections::HashMap<String, [f64; 3]>>"
+        );
+        assert_eq!(
+            name(LiteralStyle::Cpp, ty).unwrap(),
+            "std::vector<std::map<std::string, std::array<double, 3>>>"
+        );
+        assert_eq!(name(LiteralStyle::C, "string").unwrap(), "const char *");
+        assert!(name(LiteralStyle::C, ty).is_err());
+    }
+
+    #[test]
+    fn test_mismatch() {
+        assert_eq!(
+            render(LiteralStyle::JavaScript, "int[]", json!([1, "2"])),
+            Err(LiteralError::Mismatch {
+                expected: "int[]".parse().unwrap(),
+                actual: json!([1, "2"])
+            })
+        );
+    }
+}
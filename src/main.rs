@@ -1,15 +1,16 @@
 #![feature(iter_map_windows)]
 #![feature(decl_macro)]
 #![feature(async_closure)]
-#![feature(slice_split_once)]
 #![feature(string_from_utf8_lossy_owned)]
 
 use std::{env, fs, sync::Arc, time::Duration};
 
 use app::Templates;
 use axum::Router;
+use config::{BackendKind, DockerConfig};
 use docker_api::Docker;
 use langs::Languages;
+use runner::backend::ExecutionBackend;
 use sea_orm::{
     ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend, DbErr, Statement,
 };
@@ -23,6 +24,7 @@ mod app;
 mod config;
 mod format;
 mod langs;
+mod literal;
 mod routes;
 mod runner;
 mod views;
@@ -99,25 +101,37 @@ async fn main() -> eyre::Result<()> {
         _ => unreachable!("expected postgres database"),
     };
 
-    let docker = Docker::new(&config.docker.host)?;
-    tracing::info!("Connected to Docker Daemon at `{}`", &config.docker.host);
-
     let langs = Languages::new()?;
     let mut templates = Templates::new(handlebars::Handlebars::new());
+    let backends = match config.docker.backend {
+        BackendKind::Docker => docker_backends(&config.docker, &langs).await?,
+        BackendKind::Bubblewrap => {
+            let Some(bwrap) = &config.docker.bubblewrap else {
+                eyre::bail!("The bubblewrap backend needs a `docker.bubblewrap` config");
+            };
+            runner::backend::bubblewrap::backends(&config.docker, bwrap, &langs)?
+                .into_iter()
+                .map(|b| b as _)
+                .collect()
+        }
+    };
     let runner_registry =
-        runner::generate_registry(&config.docker, &docker, &langs, &mut templates).await?;
+        runner::generate_registry(&config.docker, &langs, backends, &mut templates)?;
+
+    let queue = runner::queue::ExecQueue::new(&config.docker.queue);
 
     let state = AppState {
         config,
         secrets,
         db: db.clone(),
-        docker,
         runner_registry,
+        queue,
         templates,
         langs,
     };
 
     let state = Arc::new(state);
+
     let router: Router<_> = routes::routes();
     let router: Router<()> = router.with_state(state);
     let listener = tokio::net::TcpListener::bind("localhost:3000").await?;
@@ -146,6 +160,45 @@ async fn main() -> eyre::Result<()> {
     Ok(())
 }
 
+/// Connect to the Docker daemon and set up a backend for every language,
+/// removing containers left behind by crashes now and every `reap_interval`
+async fn docker_backends(
+    cfg: &DockerConfig,
+    langs: &Languages,
+) -> eyre::Result<Vec<Arc<dyn ExecutionBackend>>> {
+    let docker = Docker::new(&cfg.host)?;
+    tracing::info!("Connected to Docker Daemon at `{}`", &cfg.host);
+
+    // nothing can be running yet, so everything left over from a previous run goes
+    let reaped =
+        runner::cleanup::reap_containers(&docker, &cfg.container_name_prefix, Duration::ZERO, &[])
+            .await?;
+    if reaped > 0 {
+        tracing::info!("Removed {} leftover containers", reaped);
+    }
+
+    let backends = runner::backend::docker::backends(cfg, &docker, langs).await?;
+
+    let mut reap_interval = tokio::time::interval(cfg.reap_interval);
+    let (prefix, min_age, pooled) = (
+        cfg.container_name_prefix.clone(),
+        cfg.reap_interval,
+        backends.clone(),
+    );
+    tokio::task::spawn(async move {
+        loop {
+            reap_interval.tick().await;
+            match runner::cleanup::reap_containers(&docker, &prefix, min_age, &pooled).await {
+                Ok(n) if n > 0 => tracing::info!("Removed {} stale containers", n),
+                Ok(_) => (),
+                Err(e) => tracing::error!("Failed to remove stale containers: {e}"),
+            }
+        }
+    });
+
+    Ok(backends.into_iter().map(|b| b as _).collect())
+}
+
 /// stolen from https://github.com/maxcountryman/tower-sessions-stores/blob/main/sqlx-store/README.md
 async fn shutdown_signal(abort: AbortHandle, db: DatabaseConnection) {
     let ctrl_c = async {
@@ -1,9 +1,31 @@
-use crate::{langs::LangType, runner::exec::GeneratorResult};
+use std::{fmt, future::Future, str::FromStr};
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::{
+    channel::{mpsc, oneshot},
+    stream::{self, BoxStream},
+    StreamExt,
+};
+
+use crate::{
+    app::Templates,
+    config::LimitOverrides,
+    runner::{
+        check::Checker,
+        compare::Comparison,
+        exec::{GeneratorCase, GeneratorResult, OutputChunk, OutputSender, RunnerResult},
+        RunnerRegistry,
+    },
+};
 
 use super::*;
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/gen", post(gen))
+    Router::new()
+        .route("/gen", post(gen))
+        .route("/gen/stream", post(gen_stream))
+        .route("/run", post(run))
+        .route("/run/stream", post(run_stream))
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -15,48 +37,502 @@ pub struct ExecRequest {
     pub hidden_cases: u16,
     pub visible_cases: u16,
     pub generate_cases: u16,
+    /// may only lower the language's limits
+    #[serde(default, skip_serializing)]
+    pub limits: LimitOverrides,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RunRequest {
+    pub content: String,
+    pub language: String,
+    pub function_name: String,
+    pub inputs: Vec<Type>,
+    pub output: Type,
+    pub cases: Vec<GeneratorCase>,
+    /// how outputs are compared to the cases', exact by default
+    #[serde(default)]
+    pub comparison: Comparison,
+    /// grades the outputs instead of comparing them to the cases'
+    #[serde(default)]
+    pub checker: Option<Checker>,
+    /// may only lower the language's limits
+    #[serde(default, skip_serializing)]
+    pub limits: LimitOverrides,
+}
+
+impl ExecRequest {
+    fn validate(&self, langs: &Languages) -> Result<(), Error> {
+        if self.generate_cases == 0 {
+            return Err(bad_request("Skipping generation of 0 cases"));
+        }
+        self.limits.validate().map_err(bad_request)?;
+        check_runnable(langs, &self.language, &self.inputs, &self.output)?;
+        Ok(())
+    }
+}
+
+impl RunRequest {
+    fn validate(&self, langs: &Languages) -> Result<(), Error> {
+        if self.cases.is_empty() {
+            return Err(bad_request("Skipping run of 0 cases"));
+        }
+        if !is_identifier(&self.function_name) {
+            return Err(bad_request(format!(
+                "Invalid function name: `{}`",
+                self.function_name
+            )));
+        }
+        self.comparison.validate().map_err(bad_request)?;
+        self.limits.validate().map_err(bad_request)?;
+        if let Some(checker) = &self.checker {
+            check_checker_lang(langs, &checker.language)?;
+        }
+        check_runnable(langs, &self.language, &self.inputs, &self.output)?;
+        for (i, case) in self.cases.iter().enumerate() {
+            case.check(i, &self.inputs, &self.output)
+                .map_err(bad_request)?;
+        }
+        Ok(())
+    }
 }
 
 async fn gen(
     mut session: Session,
     State(state): State<AppState>,
     Json(req): Json<ExecRequest>,
-) -> Result<Json<GeneratorResult>, Error> {
-    if req.generate_cases == 0 {
-        return Err(bad_request("Skipping generation of 0 cases"))
-    }
-    if session.get(&state.db).await.is_err() {
-        return Err(forbidden("Invalid session"));
-    }
+) -> Result<(QueueHeaders, Json<GeneratorResult>), Error> {
+    req.validate(&state.langs)?;
+    let user = session.user(&state.db).await?;
+
+    let ticket = wait_in_queue(&state, user.user_id).await?;
+    let res = state.runner_registry[&req.language]
+        .run_generator(&state.templates, &req, None)
+        .await?;
+    Ok((queue_headers(&ticket), Json(res)))
+}
 
-    let lang = &req.language;
-    let lang_info = state
-        .langs
-        .iter()
-        .find(|l| l.name == *lang)
-        .ok_or_else(|| not_found(format!("Unknown language: `{lang}`")))?;
+async fn run(
+    mut session: Session,
+    State(state): State<AppState>,
+    Json(req): Json<RunRequest>,
+) -> Result<(QueueHeaders, Json<RunnerResult>), Error> {
+    req.validate(&state.langs)?;
+    let user = session.user(&state.db).await?;
 
-    if lang_info.r#type != LangType::Scripting {
-        return Err(bad_request(format!(
-            "Language `{lang}` is not a scripting language"
-        )));
-    }
+    let ticket = wait_in_queue(&state, user.user_id).await?;
+    let res = run_and_check(&state.runner_registry, &state.templates, &req, None).await?;
+    Ok((queue_headers(&ticket), Json(res)))
+}
 
-    let res = state.runner_registry[lang]
-        .run_generator(&state.templates, &state.docker, &req)
+type EventStream = Sse<BoxStream<'static, Result<Event, axum::Error>>>;
+
+/// Like `/gen`, but sends a `queued` event, then `stdout` and `stderr` events as
+/// the program prints, then a `result` event
+async fn gen_stream(
+    mut session: Session,
+    State(state): State<AppState>,
+    Json(req): Json<ExecRequest>,
+) -> Result<EventStream, Error> {
+    req.validate(&state.langs)?;
+    let user = session.user(&state.db).await?;
+
+    let ticket = join_queue(&state, user.user_id)?;
+    Ok(stream_job(state, ticket, |state, output| async move {
+        state.runner_registry[&req.language]
+            .run_generator(&state.templates, &req, Some(&output))
+            .await
+    }))
+}
+
+/// Like `/run`, streamed the same way as `/gen/stream`
+async fn run_stream(
+    mut session: Session,
+    State(state): State<AppState>,
+    Json(req): Json<RunRequest>,
+) -> Result<EventStream, Error> {
+    req.validate(&state.langs)?;
+    let user = session.user(&state.db).await?;
+
+    let ticket = join_queue(&state, user.user_id)?;
+    Ok(stream_job(state, ticket, |state, output| async move {
+        run_and_check(
+            &state.runner_registry,
+            &state.templates,
+            &req,
+            Some(&output),
+        )
+        .await
+    }))
+}
+
+/// Run a solution and grade it, with the request's checker if it has one. Only
+/// the solution's output is forwarded.
+pub(super) async fn run_and_check(
+    registry: &RunnerRegistry,
+    templates: &Templates,
+    req: &RunRequest,
+    forward: Option<&OutputSender>,
+) -> eyre::Result<RunnerResult> {
+    let res = registry[&req.language]
+        .run_solution(templates, req, forward)
         .await?;
-    Ok(Json(res))
+    match (&req.checker, res) {
+        (Some(checker), RunnerResult::Success(success)) => {
+            registry[&checker.language]
+                .run_checker(templates, checker, req, success)
+                .await
+        }
+        (_, res) => Ok(res),
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "lowercase")]
+/// Run `job` in the background once `ticket` is ready, streaming its output as
+/// it arrives and its result once it is done
+fn stream_job<T, F, Fut>(state: AppState, mut ticket: Ticket, job: F) -> EventStream
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(AppState, OutputSender) -> Fut + Send + 'static,
+    Fut: Future<Output = eyre::Result<T>> + Send,
+{
+    let queued = Event::default()
+        .event("queued")
+        .json_data(serde_json::json!({ "position": ticket.position }));
+
+    let (output_tx, output_rx) = mpsc::unbounded();
+    let (result_tx, result_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        ticket.ready().await;
+        // the receiver is gone if the client disconnected, but the job still has to finish
+        let _ = result_tx.send(job(state, output_tx).await);
+    });
+
+    let output = output_rx.map(|chunk| {
+        let (event, data) = match chunk {
+            OutputChunk::Stdout(data) => ("stdout", data),
+            OutputChunk::Stderr(data) => ("stderr", data),
+        };
+        Ok(Event::default().event(event).data(data))
+    });
+    let result = async move {
+        match result_rx.await {
+            Ok(Ok(res)) => Event::default().event("result").json_data(res),
+            Ok(Err(e)) => Ok(Event::default().event("error").data(format!("{e:?}"))),
+            Err(_) => Ok(Event::default()
+                .event("error")
+                .data("Execution was cancelled")),
+        }
+    };
+
+    Sse::new(
+        stream::once(async { queued })
+            .chain(output)
+            .chain(stream::once(result))
+            .boxed(),
+    )
+    .keep_alive(KeepAlive::default())
+}
+
+/// The type of a function argument or return value, written the same way as in
+/// the frontend: `int`, `string[]`, `float[3]`, `map<int>`...
+///
+/// Array suffixes nest outwards, so `int[][3]` is an array of 3 `int[]`s.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum Type {
     Bool,
     Int,
     Float,
     String,
-    // #[serde(untagged)]
-    // Array(Box<Type>, Option<u16>),
-    // TODO: array support
-    // map support?
+    /// An array, with a fixed length if one was specified
+    Array(Box<Type>, Option<u16>),
+    /// A map from strings to values
+    Map(Box<Type>),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ParseTypeError {
+    #[error("Unknown type: `{0}`")]
+    Unknown(String),
+    #[error("Unknown complex type: `{0}`")]
+    UnknownComplex(String),
+    #[error("Invalid array length: `{0}`")]
+    ArrayLength(String),
+    #[error("Types may not be longer than {MAX_TYPE_LENGTH} characters")]
+    TooLong,
+}
+
+/// Mirrors `typeSchema` in the frontend. Also bounds how deeply types nest,
+/// which parsing recurses through.
+const MAX_TYPE_LENGTH: usize = 30;
+
+impl Type {
+    /// Whether `value` is a valid instance of this type
+    pub fn check(&self, value: &serde_json::Value) -> bool {
+        use serde_json::Value;
+
+        match (self, value) {
+            (Type::Bool, Value::Bool(_)) => true,
+            // every language renders ints as 64-bit signed literals
+            (Type::Int, Value::Number(n)) => n.is_i64(),
+            (Type::Float, Value::Number(_)) => true,
+            (Type::String, Value::String(_)) => true,
+            (Type::Array(elem, len), Value::Array(values)) => {
+                len.is_none_or(|len| values.len() == len as usize)
+                    && values.iter().all(|v| elem.check(v))
+            }
+            (Type::Map(value), Value::Object(map)) => map.values().all(|v| value.check(v)),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Type {
+    type Err = ParseTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.len() > MAX_TYPE_LENGTH {
+            return Err(ParseTypeError::TooLong);
+        }
+
+        if let Some((elem, len)) = s.strip_suffix(']').and_then(|s| s.rsplit_once('[')) {
+            let len = match len.trim() {
+                "" => None,
+                len => Some(
+                    len.parse()
+                        .map_err(|_| ParseTypeError::ArrayLength(len.to_string()))?,
+                ),
+            };
+            return Ok(Type::Array(Box::new(elem.parse()?), len));
+        }
+
+        if let Some((name, inner)) = s.strip_suffix('>').and_then(|s| s.split_once('<')) {
+            return match name.trim() {
+                "map" => Ok(Type::Map(Box::new(inner.parse()?))),
+                name => Err(ParseTypeError::UnknownComplex(name.to_string())),
+            };
+        }
+
+        match s {
+            "bool" => Ok(Type::Bool),
+            "int" => Ok(Type::Int),
+            "float" => Ok(Type::Float),
+            "string" => Ok(Type::String),
+            _ => Err(ParseTypeError::Unknown(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::String => write!(f, "string"),
+            Type::Array(elem, None) => write!(f, "{elem}[]"),
+            Type::Array(elem, Some(len)) => write!(f, "{elem}[{len}]"),
+            Type::Map(value) => write!(f, "map<{value}>"),
+        }
+    }
+}
+
+impl TryFrom<String> for Type {
+    type Error = ParseTypeError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Type> for String {
+    fn from(value: Type) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        langs::Languages,
+        runner::{
+            backend::fake::{self, ReplayBackend},
+            exec::RunnerResult,
+        },
+    };
+
+    use super::{run_and_check, ExecRequest, ParseTypeError, RunRequest, Type};
+
+    #[test]
+    fn test_parse_type() {
+        let int = || Box::new(Type::Int);
+        let cases = [
+            ("int", Type::Int),
+            ("string[]", Type::Array(Box::new(Type::String), None)),
+            ("float[3]", Type::Array(Box::new(Type::Float), Some(3))),
+            (
+                "int[][3]",
+                Type::Array(Box::new(Type::Array(int(), None)), Some(3)),
+            ),
+            ("map<int>", Type::Map(int())),
+            ("map<int[]>", Type::Map(Box::new(Type::Array(int(), None)))),
+            (
+                "map<bool>[]",
+                Type::Array(Box::new(Type::Map(Box::new(Type::Bool))), None),
+            ),
+        ];
+
+        for (s, ty) in cases {
+            assert_eq!(s.parse::<Type>(), Ok(ty.clone()));
+            assert_eq!(ty.to_string(), s);
+
+            let json = serde_json::to_value(&ty).unwrap();
+            assert_eq!(json, serde_json::json!(s));
+            assert_eq!(serde_json::from_value::<Type>(json).unwrap(), ty);
+        }
+    }
+
+    #[test]
+    fn test_check_type() {
+        use serde_json::json;
+
+        let matrix: Type = "int[2][]".parse().unwrap();
+        assert!(matrix.check(&json!([[1, 2], [3, 4]])));
+        assert!(matrix.check(&json!([])));
+        assert!(!matrix.check(&json!([[1, 2], [3]])));
+        assert!(!matrix.check(&json!([[1, 2.5]])));
+
+        let counts: Type = "map<int>".parse().unwrap();
+        assert!(counts.check(&json!({ "a": 1, "b": 2 })));
+        assert!(!counts.check(&json!({ "a": "1" })));
+        assert!(!counts.check(&json!([1])));
+
+        assert!(Type::Float.check(&json!(1)));
+        assert!(!Type::Int.check(&json!(1.0)));
+        assert!(Type::Int.check(&json!(i64::MIN)));
+        assert!(!Type::Int.check(&json!(i64::MAX as u64 + 1)));
+        assert!(!Type::String.check(&json!(null)));
+    }
+
+    #[test]
+    fn test_parse_type_errors() {
+        assert_eq!(
+            "integer".parse::<Type>(),
+            Err(ParseTypeError::Unknown("integer".to_string()))
+        );
+        assert_eq!(
+            "set<int>".parse::<Type>(),
+            Err(ParseTypeError::UnknownComplex("set".to_string()))
+        );
+        assert_eq!(
+            "int[-1]".parse::<Type>(),
+            Err(ParseTypeError::ArrayLength("-1".to_string()))
+        );
+        assert!(serde_json::from_str::<Type>("\"int[\"").is_err());
+        assert_eq!(
+            format!("int{}", "[]".repeat(100_000)).parse::<Type>(),
+            Err(ParseTypeError::TooLong)
+        );
+    }
+
+    fn gen_request(language: &str, inputs: &str) -> ExecRequest {
+        serde_json::from_value(json!({
+            "content": "",
+            "language": language,
+            "inputs": [inputs],
+            "output": "int",
+            "hidden_cases": 0,
+            "visible_cases": 0,
+            "generate_cases": 1,
+        }))
+        .unwrap()
+    }
+
+    fn run_request(checker: Option<&str>) -> RunRequest {
+        serde_json::from_value(json!({
+            "content": "",
+            "language": "python",
+            "function_name": "add",
+            "inputs": ["int", "int"],
+            "output": "int",
+            "cases": [{ "input": [1, 1], "output": 2 }],
+            "checker": checker.map(|language| json!({ "language": language, "content": "" })),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let langs = Languages::new().unwrap();
+        let status = |res: Result<(), crate::views::Error>| res.map_err(|e| e.status);
+
+        assert_eq!(
+            status(gen_request("python", "int").validate(&langs)),
+            Ok(())
+        );
+        assert_eq!(
+            status(gen_request("cobol", "int").validate(&langs)),
+            Err(StatusCode::NOT_FOUND)
+        );
+        // C has no arrays
+        assert_eq!(
+            status(gen_request("c", "int[]").validate(&langs)),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        let mut zero = gen_request("python", "int");
+        zero.generate_cases = 0;
+        assert_eq!(status(zero.validate(&langs)), Err(StatusCode::BAD_REQUEST));
+
+        assert_eq!(status(run_request(Some("python")).validate(&langs)), Ok(()));
+        assert_eq!(
+            status(run_request(Some("rust")).validate(&langs)),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        let mut name = run_request(None);
+        name.function_name = "1add".to_string();
+        assert_eq!(status(name.validate(&langs)), Err(StatusCode::BAD_REQUEST));
+        let mut case = run_request(None);
+        case.cases[0].input.pop();
+        assert_eq!(status(case.validate(&langs)), Err(StatusCode::BAD_REQUEST));
+        let mut case = run_request(None);
+        case.cases[0].output = json!("2");
+        assert_eq!(status(case.validate(&langs)), Err(StatusCode::BAD_REQUEST));
+        let mut epsilon = run_request(None);
+        epsilon.comparison.relative_epsilon = f64::NAN;
+        assert_eq!(
+            status(epsilon.validate(&langs)),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_and_check() {
+        let replay = ReplayBackend::new([
+            fake::exited(0, Some(br#"[{"output": 3}]"#)),
+            fake::exited(0, Some(br#"[{"passed": true}]"#)),
+            fake::exited(1, None),
+        ]);
+        let (templates, reg) = fake::registry(|_| replay.clone());
+        let req = run_request(Some("python"));
+
+        // the checker overrules the exact comparison
+        let RunnerResult::Success(success) =
+            run_and_check(&reg, &templates, &req, None).await.unwrap()
+        else {
+            panic!()
+        };
+        assert_eq!(success.passed, 1);
+        assert_eq!(replay.jobs.lock().unwrap().len(), 2);
+
+        // a solution that fails is not checked
+        assert!(matches!(
+            run_and_check(&reg, &templates, &req, None).await.unwrap(),
+            RunnerResult::Err(e) if e.exit_code == 1
+        ));
+        assert_eq!(replay.jobs.lock().unwrap().len(), 3);
+    }
 }
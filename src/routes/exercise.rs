@@ -0,0 +1,262 @@
+use super::*;
+
+use axum::extract::Path;
+use chrono::Utc;
+use entity::exercise;
+use sea_orm::{ActiveModelTrait, IntoActiveModel, ModelTrait, Set};
+use uuid::Uuid;
+
+use crate::{
+    routes::{exec::Type, test_case},
+    runner::{check::Checker, compare::Comparison},
+    views::exercise::ExerciseView,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list).post(create))
+        .route("/:id", get(read).put(update).delete(remove))
+        .nest("/:id/cases", test_case::routes())
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FunctionArg {
+    pub arg: String,
+    pub r#type: Type,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExerciseRequest {
+    pub title: String,
+    pub description: String,
+    pub function_name: String,
+    pub args: Vec<FunctionArg>,
+    pub output: Type,
+    /// how solutions' outputs are graded
+    #[serde(default)]
+    pub comparison: Comparison,
+    pub solution: String,
+    pub solution_lang: String,
+    pub starting_code: Option<String>,
+    pub generator_lang: Option<String>,
+    pub generator: Option<String>,
+    /// grades solutions instead of comparing their outputs, see [`Checker`]
+    pub checker_lang: Option<String>,
+    pub checker: Option<String>,
+    #[serde(default)]
+    pub generated_table: Vec<serde_json::Value>,
+}
+
+const MAX_LONG_STRING: usize = 6000;
+
+impl ExerciseRequest {
+    /// Mirrors `exerciseSchema` in the frontend
+    fn validate(&self, langs: &Languages) -> Result<(), Error> {
+        let title = self.title.trim();
+        if !(5..=32).contains(&title.chars().count()) {
+            return Err(bad_request("Title must be between 5 and 32 characters"));
+        }
+        if !(20..=MAX_LONG_STRING).contains(&self.description.chars().count()) {
+            return Err(bad_request(
+                "Description must be between 20 and 6000 characters",
+            ));
+        }
+        if !is_identifier(&self.function_name) {
+            return Err(bad_request(format!(
+                "Invalid function name: `{}`",
+                self.function_name
+            )));
+        }
+        if !(1..=8).contains(&self.args.len()) {
+            return Err(bad_request("Exercises must take between 1 and 8 arguments"));
+        }
+        if let Some(arg) = self.args.iter().find(|a| !is_identifier(&a.arg)) {
+            return Err(bad_request(format!("Invalid argument name: `{}`", arg.arg)));
+        }
+        self.comparison.validate().map_err(bad_request)?;
+        let long_strings = [
+            Some(&self.solution),
+            self.starting_code.as_ref(),
+            self.generator.as_ref(),
+            self.checker.as_ref(),
+        ];
+        if long_strings
+            .into_iter()
+            .flatten()
+            .any(|s| s.chars().count() > MAX_LONG_STRING)
+        {
+            return Err(bad_request("Code may not be longer than 6000 characters"));
+        }
+        if self.generated_table.len() > 500 {
+            return Err(bad_request(
+                "Generated table may not have more than 500 rows",
+            ));
+        }
+
+        let inputs: Vec<Type> = self.args.iter().map(|a| a.r#type.clone()).collect();
+        let runnable = std::iter::once(&self.solution_lang).chain(&self.generator_lang);
+        for lang in runnable {
+            check_runnable(langs, lang, &inputs, &self.output)?;
+        }
+        match (&self.checker_lang, &self.checker) {
+            (Some(lang), Some(_)) => {
+                check_checker_lang(langs, lang)?;
+            }
+            (None, None) => {}
+            _ => return Err(bad_request("Checkers need both a language and code")),
+        }
+
+        Ok(())
+    }
+}
+
+async fn list(
+    mut session: Session,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ExerciseView>>, Error> {
+    let user = session.user(&state.db).await?;
+    let exercises = exercise::Model::list_by_user(&state.db, user.user_id).await?;
+
+    Ok(Json(
+        exercises
+            .iter()
+            .map(|e| ExerciseView::new(e, user.user_id))
+            .collect(),
+    ))
+}
+
+async fn create(
+    mut session: Session,
+    State(state): State<AppState>,
+    Json(req): Json<ExerciseRequest>,
+) -> Result<(StatusCode, Json<ExerciseView>), Error> {
+    let user = session.user(&state.db).await?;
+    req.validate(&state.langs)?;
+
+    let now = Utc::now().naive_utc();
+    let exercise = exercise::Model {
+        exercise_id: Uuid::now_v7(),
+        user_id: user.user_id,
+        title: req.title.trim().to_string(),
+        description: req.description,
+        function_name: req.function_name,
+        args: serde_json::to_value(&req.args).map_err(internal)?,
+        output: serde_json::to_value(&req.output).map_err(internal)?,
+        comparison: serde_json::to_value(&req.comparison).map_err(internal)?,
+        solution: req.solution,
+        solution_lang: req.solution_lang,
+        starting_code: req.starting_code,
+        generator_lang: req.generator_lang,
+        generator: req.generator,
+        checker_lang: req.checker_lang,
+        checker: req.checker,
+        generated_table: serde_json::Value::Array(req.generated_table),
+        created: now,
+        updated: now,
+    };
+    exercise.clone().insert(&state.db).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ExerciseView::new(&exercise, user.user_id)),
+    ))
+}
+
+async fn read(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path(exercise_id): Path<Uuid>,
+) -> Result<Json<ExerciseView>, Error> {
+    let user = session.user(&state.db).await?;
+    let exercise = exercise::Model::get(&state.db, exercise_id)
+        .await?
+        .ok_or_else(|| not_found("Exercise not found"))?;
+
+    Ok(Json(ExerciseView::new(&exercise, user.user_id)))
+}
+
+/// The argument and output types of an exercise's function
+pub(super) fn signature(exercise: &exercise::Model) -> Result<(Vec<Type>, Type), Error> {
+    let args: Vec<FunctionArg> = serde_json::from_value(exercise.args.clone()).map_err(internal)?;
+    let output: Type = serde_json::from_value(exercise.output.clone()).map_err(internal)?;
+    Ok((args.into_iter().map(|a| a.r#type).collect(), output))
+}
+
+/// The checker that grades an exercise's solutions, if it has one
+pub(super) fn checker(exercise: &exercise::Model) -> Option<Checker> {
+    Some(Checker {
+        language: exercise.checker_lang.clone()?,
+        content: exercise.checker.clone()?,
+    })
+}
+
+/// Get an exercise, making sure that `user_id` is allowed to modify it
+pub(super) async fn get_owned(
+    state: &AppState,
+    exercise_id: Uuid,
+    user_id: Uuid,
+) -> Result<exercise::Model, Error> {
+    let exercise = exercise::Model::get(&state.db, exercise_id)
+        .await?
+        .ok_or_else(|| not_found("Exercise not found"))?;
+    if exercise.user_id != user_id {
+        return Err(forbidden("Exercise belongs to another user"));
+    }
+    Ok(exercise)
+}
+
+async fn update(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path(exercise_id): Path<Uuid>,
+    Json(req): Json<ExerciseRequest>,
+) -> Result<Json<ExerciseView>, Error> {
+    let user = session.user(&state.db).await?;
+    req.validate(&state.langs)?;
+    let exercise = get_owned(&state, exercise_id, user.user_id).await?;
+
+    // submissions would be run with cases of the old signature
+    let inputs: Vec<Type> = req.args.iter().map(|a| a.r#type.clone()).collect();
+    let cases = entity::test_case::Model::list_by_exercise(&state.db, exercise_id).await?;
+    for (i, case) in cases.iter().enumerate() {
+        test_case::generator_case(case)
+            .check(i, &inputs, &req.output)
+            .map_err(|e| {
+                bad_request(format!(
+                    "{e}. Replace the test cases before changing the signature"
+                ))
+            })?;
+    }
+
+    let mut active = exercise.into_active_model();
+    active.title = Set(req.title.trim().to_string());
+    active.description = Set(req.description);
+    active.function_name = Set(req.function_name);
+    active.args = Set(serde_json::to_value(&req.args).map_err(internal)?);
+    active.output = Set(serde_json::to_value(&req.output).map_err(internal)?);
+    active.comparison = Set(serde_json::to_value(&req.comparison).map_err(internal)?);
+    active.solution = Set(req.solution);
+    active.solution_lang = Set(req.solution_lang);
+    active.starting_code = Set(req.starting_code);
+    active.generator_lang = Set(req.generator_lang);
+    active.generator = Set(req.generator);
+    active.checker_lang = Set(req.checker_lang);
+    active.checker = Set(req.checker);
+    active.generated_table = Set(serde_json::Value::Array(req.generated_table));
+    active.updated = Set(Utc::now().naive_utc());
+    let exercise = active.update(&state.db).await?;
+
+    Ok(Json(ExerciseView::new(&exercise, user.user_id)))
+}
+
+async fn remove(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path(exercise_id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let user = session.user(&state.db).await?;
+    let exercise = get_owned(&state, exercise_id, user.user_id).await?;
+    exercise.delete(&state.db).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
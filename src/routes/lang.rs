@@ -0,0 +1,22 @@
+use super::*;
+
+use axum::extract::Path;
+
+use crate::views::lang::LangView;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list))
+        .route("/:name", get(read))
+}
+
+async fn list(State(state): State<AppState>) -> Json<Vec<LangView>> {
+    Json(state.langs.iter().map(LangView::from).collect())
+}
+
+async fn read(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<LangView>, Error> {
+    Ok(Json(find_lang(&state.langs, &name)?.into()))
+}
@@ -2,7 +2,7 @@ use axum::{
     body::Body,
     extract::State,
     http::{Response, StatusCode},
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
@@ -10,15 +10,21 @@ use std::time::Duration;
 
 use eyre::Context;
 
+use crate::langs::{LangInfo, Languages};
+use crate::runner::queue::{QueueError, Ticket, QUEUE_POSITION};
 use crate::views::{
     auth::{login, UserAvatar},
-    bad_request, forbidden, internal, not_found,
+    bad_request, forbidden, internal, not_found, service_unavailable,
     session::Session,
-    unauthorized, Error,
+    too_many_requests, unauthorized, Error,
 };
 
 pub mod auth;
 pub mod exec;
+pub mod exercise;
+pub mod lang;
+pub mod submission;
+pub mod test_case;
 
 pub type AppState = std::sync::Arc<crate::AppState>;
 
@@ -26,6 +32,85 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         .nest("/auth", auth::routes())
         .nest("/exec", exec::routes())
+        .nest("/exercises", exercise::routes())
+        .nest("/langs", lang::routes())
+        .nest("/submissions", submission::routes())
         .layer(tower_http::trace::TraceLayer::new_for_http())
         .layer(tower_http::cors::CorsLayer::very_permissive())
 }
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn find_lang<'a>(langs: &'a Languages, lang: &str) -> Result<&'a LangInfo, Error> {
+    langs
+        .iter()
+        .find(|l| l.name == lang)
+        .ok_or_else(|| not_found(format!("Unknown language: `{lang}`")))
+}
+
+/// Find a language that can run a function taking `inputs` and returning `output`
+fn check_runnable<'a>(
+    langs: &'a Languages,
+    lang: &str,
+    inputs: &[exec::Type],
+    output: &exec::Type,
+) -> Result<&'a LangInfo, Error> {
+    let lang_info = find_lang(langs, lang)?;
+    if !lang_info.r#type.is_runnable() {
+        return Err(bad_request(format!(
+            "Language `{lang}` is not a programming language"
+        )));
+    }
+    if let Some(ty) = inputs
+        .iter()
+        .chain([output])
+        .find_map(|ty| lang_info.unsupported(ty))
+    {
+        return Err(bad_request(format!(
+            "Language `{lang}` does not support the type `{ty}`"
+        )));
+    }
+    Ok(lang_info)
+}
+
+/// Find a language that checkers can be written in
+fn check_checker_lang<'a>(langs: &'a Languages, lang: &str) -> Result<&'a LangInfo, Error> {
+    let lang_info = find_lang(langs, lang)?;
+    if !lang_info.checkers {
+        return Err(bad_request(format!(
+            "Language `{lang}` does not support checkers"
+        )));
+    }
+    Ok(lang_info)
+}
+
+/// Queue one of `user_id`'s jobs, or turn it away if the queue is full
+fn join_queue(state: &AppState, user_id: uuid::Uuid) -> Result<Ticket, Error> {
+    let retry_after = state.config.docker.queue.retry_after;
+    state.queue.join(user_id).map_err(|e| {
+        match e {
+            QueueError::UserLimit(_) => too_many_requests(e),
+            QueueError::Full => service_unavailable(e),
+        }
+        .retry_after(retry_after)
+    })
+}
+
+/// Wait for a turn to run one of `user_id`'s jobs
+async fn wait_in_queue(state: &AppState, user_id: uuid::Uuid) -> Result<Ticket, Error> {
+    let mut ticket = join_queue(state, user_id)?;
+    ticket.ready().await;
+    Ok(ticket)
+}
+
+type QueueHeaders = [(&'static str, String); 1];
+
+fn queue_headers(ticket: &Ticket) -> QueueHeaders {
+    [(QUEUE_POSITION, ticket.position.to_string())]
+}
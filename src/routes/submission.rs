@@ -0,0 +1,188 @@
+use super::*;
+
+use axum::extract::{Path, Query};
+use chrono::Utc;
+use entity::{exercise, sea_orm_active_enums::SubmissionStatus, submission, test_case};
+use uuid::Uuid;
+
+use crate::{
+    routes::{
+        exec::{run_and_check, RunRequest},
+        exercise::{checker, signature},
+        test_case::generator_case,
+    },
+    runner::exec::{Limit, RunnerResult},
+    views::submission::{SubmissionCase, SubmissionView},
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list).post(submit))
+        .route("/:id", get(read))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitRequest {
+    pub exercise_id: Uuid,
+    pub language: String,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    exercise: Uuid,
+}
+
+const WITHHELD_OUTPUT: &str = "The output is withheld because it could reveal hidden test \
+    cases, run the solution against the visible ones to see it.";
+
+/// Grade `content` against every test case of an exercise and record the attempt
+async fn submit(
+    mut session: Session,
+    State(state): State<AppState>,
+    Json(req): Json<SubmitRequest>,
+) -> Result<(StatusCode, QueueHeaders, Json<SubmissionView>), Error> {
+    let user = session.user(&state.db).await?;
+    let exercise = exercise::Model::get(&state.db, req.exercise_id)
+        .await?
+        .ok_or_else(|| not_found("Exercise not found"))?;
+
+    let (inputs, output) = signature(&exercise)?;
+    check_runnable(&state.langs, &req.language, &inputs, &output)?;
+    let checker = checker(&exercise);
+    if let Some(checker) = &checker {
+        check_checker_lang(&state.langs, &checker.language)?;
+    }
+
+    let cases = test_case::Model::list_by_exercise(&state.db, exercise.exercise_id).await?;
+    if cases.is_empty() {
+        return Err(bad_request("Exercise has no test cases"));
+    }
+
+    let run = RunRequest {
+        content: req.content,
+        language: req.language,
+        function_name: exercise.function_name.clone(),
+        inputs,
+        output,
+        cases: cases.iter().map(generator_case).collect(),
+        comparison: serde_json::from_value(exercise.comparison.clone()).map_err(internal)?,
+        checker,
+        limits: Default::default(),
+    };
+
+    let ticket = wait_in_queue(&state, user.user_id).await?;
+    let res = run_and_check(&state.runner_registry, &state.templates, &run, None).await?;
+    // free the slot before talking to the database
+    let headers = queue_headers(&ticket);
+    drop(ticket);
+    let (status, results, mut stdout, mut stderr) = match res {
+        RunnerResult::Success(s) => {
+            let status = match s.passed == s.cases.len() {
+                true => SubmissionStatus::Passed,
+                false => SubmissionStatus::Failed,
+            };
+            let results: Vec<SubmissionCase> = s
+                .cases
+                .into_iter()
+                .zip(&cases)
+                .map(|(result, case)| SubmissionCase {
+                    hidden: case.hidden,
+                    result,
+                })
+                .collect();
+            (status, results, s.stdout, s.stderr)
+        }
+        RunnerResult::LimitExceeded(e) => {
+            let status = match e.limit {
+                Limit::Time => SubmissionStatus::TimeLimitExceeded,
+                Limit::Memory => SubmissionStatus::MemoryLimitExceeded,
+                Limit::Output => SubmissionStatus::OutputLimitExceeded,
+            };
+            (status, vec![], e.stdout, e.stderr)
+        }
+        RunnerResult::CompileError(e) => (
+            SubmissionStatus::CompileError,
+            vec![],
+            String::new(),
+            e.diagnostics,
+        ),
+        RunnerResult::Err(e) => (SubmissionStatus::Error, vec![], e.stdout, e.stderr),
+        // what the checker printed is up to the exercise's author, who may not
+        // want it shown
+        RunnerResult::CheckerError(_) => (
+            SubmissionStatus::CheckerError,
+            vec![],
+            String::new(),
+            String::new(),
+        ),
+    };
+    // every case is built into the harness, which the solution can print, and
+    // compilers quote its literals back when the solution does not fit them
+    let owned = exercise.user_id == user.user_id;
+    let printed = !stdout.is_empty() || !stderr.is_empty();
+    if printed && !owned && cases.iter().any(|c| c.hidden) {
+        stdout = String::new();
+        stderr = WITHHELD_OUTPUT.to_string();
+    }
+
+    let submission = submission::Model {
+        submission_id: Uuid::now_v7(),
+        user_id: user.user_id,
+        exercise_id: exercise.exercise_id,
+        language: run.language,
+        code: run.content,
+        created: Utc::now().naive_utc(),
+        status,
+        results: serde_json::to_value(&results).map_err(internal)?,
+        stdout,
+        stderr,
+    };
+    submission.clone().insert(&state.db).await?;
+
+    let view = SubmissionView::new(&submission, owned).map_err(internal)?;
+    Ok((StatusCode::CREATED, headers, Json(view)))
+}
+
+async fn list(
+    mut session: Session,
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<SubmissionView>>, Error> {
+    let user = session.user(&state.db).await?;
+    let exercise = exercise::Model::get(&state.db, query.exercise)
+        .await?
+        .ok_or_else(|| not_found("Exercise not found"))?;
+
+    let submissions =
+        submission::Model::list_by_user(&state.db, user.user_id, exercise.exercise_id).await?;
+    let views = submissions
+        .iter()
+        .map(|s| SubmissionView::new(s, exercise.user_id == user.user_id))
+        .collect::<Result<_, _>>()
+        .map_err(internal)?;
+    Ok(Json(views))
+}
+
+async fn read(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path(submission_id): Path<Uuid>,
+) -> Result<Json<SubmissionView>, Error> {
+    let user = session.user(&state.db).await?;
+    let submission = submission::Model::get(&state.db, submission_id)
+        .await?
+        .ok_or_else(|| not_found("Submission not found"))?;
+    let exercise = exercise::Model::get(&state.db, submission.exercise_id)
+        .await?
+        .ok_or_else(|| not_found("Exercise not found"))?;
+
+    // submissions are visible to whoever made them and the owner of the exercise
+    let owned = exercise.user_id == user.user_id;
+    if submission.user_id != user.user_id && !owned {
+        return Err(forbidden("Submission belongs to another user"));
+    }
+
+    let view = SubmissionView::new(&submission, owned).map_err(internal)?;
+    Ok(Json(view))
+}
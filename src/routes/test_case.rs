@@ -0,0 +1,187 @@
+use super::*;
+
+use axum::extract::Path;
+use entity::{exercise, test_case};
+use sea_orm::{ActiveModelTrait, IntoActiveModel, ModelTrait, Set};
+use uuid::Uuid;
+
+use crate::{
+    routes::exercise::{get_owned, signature},
+    runner::exec::GeneratorCase,
+    views::exercise::TestCaseView,
+};
+
+/// Nested under `/exercises/:id/cases`
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list).post(add).put(accept))
+        .route("/:case_id", put(edit).delete(remove))
+}
+
+const MAX_CASES: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct TestCaseRequest {
+    #[serde(flatten)]
+    pub case: GeneratorCase,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// Accept the cases produced by a generator run as the exercise's test cases
+#[derive(Debug, Deserialize)]
+pub struct AcceptRequest {
+    pub cases: Vec<GeneratorCase>,
+    /// the first `visible_cases` cases are shown to students, the rest are hidden
+    pub visible_cases: usize,
+}
+
+/// A stored test case, as the runner takes it
+pub(super) fn generator_case(case: &test_case::Model) -> GeneratorCase {
+    GeneratorCase {
+        input: case.input.as_array().cloned().unwrap_or_default(),
+        output: case.output.clone(),
+    }
+}
+
+/// Make sure `case` matches the signature of the exercise's function
+fn check_case(exercise: &exercise::Model, index: usize, case: &GeneratorCase) -> Result<(), Error> {
+    let (inputs, output) = signature(exercise)?;
+    case.check(index, &inputs, &output).map_err(bad_request)
+}
+
+async fn list(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path(exercise_id): Path<Uuid>,
+) -> Result<Json<Vec<TestCaseView>>, Error> {
+    let user = session.user(&state.db).await?;
+    let exercise = exercise::Model::get(&state.db, exercise_id)
+        .await?
+        .ok_or_else(|| not_found("Exercise not found"))?;
+    let owned = exercise.user_id == user.user_id;
+
+    let cases = test_case::Model::list_by_exercise(&state.db, exercise_id).await?;
+    Ok(Json(
+        cases
+            .iter()
+            .filter(|c| owned || !c.hidden)
+            .map(TestCaseView::new)
+            .collect(),
+    ))
+}
+
+async fn accept(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path(exercise_id): Path<Uuid>,
+    Json(req): Json<AcceptRequest>,
+) -> Result<Json<Vec<TestCaseView>>, Error> {
+    let user = session.user(&state.db).await?;
+    let exercise = get_owned(&state, exercise_id, user.user_id).await?;
+
+    if req.cases.len() > MAX_CASES {
+        return Err(bad_request(format!(
+            "Exercises may not have more than {MAX_CASES} test cases"
+        )));
+    }
+    let (inputs, output) = signature(&exercise)?;
+    for (i, case) in req.cases.iter().enumerate() {
+        case.check(i, &inputs, &output).map_err(bad_request)?;
+    }
+
+    let cases: Vec<test_case::Model> = req
+        .cases
+        .into_iter()
+        .enumerate()
+        .map(|(i, case)| test_case::Model {
+            test_case_id: Uuid::now_v7(),
+            exercise_id,
+            position: i as i32,
+            input: serde_json::Value::Array(case.input),
+            output: case.output,
+            hidden: i >= req.visible_cases,
+        })
+        .collect();
+    test_case::Model::replace_all(&state.db, exercise_id, cases.clone()).await?;
+
+    Ok(Json(cases.iter().map(TestCaseView::new).collect()))
+}
+
+async fn add(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path(exercise_id): Path<Uuid>,
+    Json(req): Json<TestCaseRequest>,
+) -> Result<(StatusCode, Json<TestCaseView>), Error> {
+    let user = session.user(&state.db).await?;
+    let exercise = get_owned(&state, exercise_id, user.user_id).await?;
+
+    let cases = test_case::Model::list_by_exercise(&state.db, exercise_id).await?;
+    if cases.len() >= MAX_CASES {
+        return Err(bad_request(format!(
+            "Exercises may not have more than {MAX_CASES} test cases"
+        )));
+    }
+
+    let position = cases.last().map_or(0, |c| c.position + 1);
+    check_case(&exercise, position as usize, &req.case)?;
+
+    let case = test_case::Model {
+        test_case_id: Uuid::now_v7(),
+        exercise_id,
+        position,
+        input: serde_json::Value::Array(req.case.input),
+        output: req.case.output,
+        hidden: req.hidden,
+    };
+    case.clone().insert(&state.db).await?;
+
+    Ok((StatusCode::CREATED, Json(TestCaseView::new(&case))))
+}
+
+/// Get a test case, making sure it belongs to an exercise `user_id` may modify
+async fn get_owned_case(
+    state: &AppState,
+    exercise_id: Uuid,
+    test_case_id: Uuid,
+    user_id: Uuid,
+) -> Result<(exercise::Model, test_case::Model), Error> {
+    let exercise = get_owned(state, exercise_id, user_id).await?;
+    let case = test_case::Model::get(&state.db, test_case_id)
+        .await?
+        .filter(|c| c.exercise_id == exercise_id)
+        .ok_or_else(|| not_found("Test case not found"))?;
+    Ok((exercise, case))
+}
+
+async fn edit(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path((exercise_id, test_case_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<TestCaseRequest>,
+) -> Result<Json<TestCaseView>, Error> {
+    let user = session.user(&state.db).await?;
+    let (exercise, case) = get_owned_case(&state, exercise_id, test_case_id, user.user_id).await?;
+    check_case(&exercise, case.position as usize, &req.case)?;
+
+    let mut active = case.into_active_model();
+    active.input = Set(serde_json::Value::Array(req.case.input));
+    active.output = Set(req.case.output);
+    active.hidden = Set(req.hidden);
+    let case = active.update(&state.db).await?;
+
+    Ok(Json(TestCaseView::new(&case)))
+}
+
+async fn remove(
+    mut session: Session,
+    State(state): State<AppState>,
+    Path((exercise_id, test_case_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    let user = session.user(&state.db).await?;
+    let (_, case) = get_owned_case(&state, exercise_id, test_case_id, user.user_id).await?;
+    case.delete(&state.db).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
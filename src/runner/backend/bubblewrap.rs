@@ -0,0 +1,311 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
+};
+
+use eyre::{ensure, Context};
+use futures::{future::BoxFuture, stream};
+use tokio::{fs, process::Command};
+use uuid::Uuid;
+
+use crate::{
+    config::{BubblewrapConfig, DockerConfig, SandboxConfig},
+    langs::{LangInfo, Languages},
+    runner::{
+        exec::{Limit, Output},
+        Phase,
+    },
+};
+
+use super::{collect_output, read_chunks, read_collected, ExecutionBackend, Job, RawChunk};
+
+/// Runs every program with `bwrap` in new namespaces, inside a cgroup of its own
+/// that enforces the limits Docker otherwise would. Programs see the host's
+/// toolchains, or the language's root filesystem if one is configured.
+pub struct BubblewrapBackend {
+    pub lang: LangInfo,
+    pub cfg: BubblewrapConfig,
+    pub sandbox: SandboxConfig,
+    /// starts the names of every execution's work dir and cgroup, like docker
+    /// container names
+    pub name_prefix: String,
+}
+
+/// Set up a backend for every language, in the same order, after removing what
+/// previous runs of the server left behind
+pub fn backends(
+    cfg: &DockerConfig,
+    bwrap: &BubblewrapConfig,
+    langs: &Languages,
+) -> eyre::Result<Vec<Arc<BubblewrapBackend>>> {
+    let subtree = bwrap.cgroup.join("cgroup.subtree_control");
+    let controllers = std::fs::read_to_string(&subtree)
+        .with_context(|| format!("Could not read `{}`", subtree.display()))?;
+    for controller in ["cpu", "memory", "pids"] {
+        ensure!(
+            controllers.split_whitespace().any(|c| c == controller),
+            "The `{controller}` controller is not enabled in `{}`",
+            subtree.display()
+        );
+    }
+
+    let reaped = reap_sandboxes(&bwrap.cgroup, &cfg.container_name_prefix)?;
+    if reaped > 0 {
+        tracing::info!("Removed {} leftover sandboxes", reaped);
+    }
+
+    langs
+        .iter()
+        .map(|lang| {
+            if let Some(root) = &bwrap.root {
+                ensure!(
+                    root.join(&lang.name).is_dir(),
+                    "Expected a root filesystem for `{}` in `{}`",
+                    lang.name,
+                    root.display()
+                );
+            }
+            if lang.seccomp.is_some() {
+                tracing::warn!(
+                    "Ignoring the seccomp profile of `{}`, which is only understood by Docker",
+                    lang.name
+                );
+            }
+
+            Ok(Arc::new(BubblewrapBackend {
+                lang: lang.clone(),
+                cfg: bwrap.clone(),
+                sandbox: cfg.sandbox.clone(),
+                name_prefix: cfg.container_name_prefix.clone(),
+            }))
+        })
+        .collect()
+}
+
+/// Remove the work dirs and cgroups of executions that were cut short by the
+/// server exiting, which nothing else is running at startup
+fn reap_sandboxes(cgroup: &Path, prefix: &str) -> eyre::Result<usize> {
+    let mut reaped = 0;
+    for dir in [cgroup.to_path_buf(), std::env::temp_dir()] {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let named = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(prefix));
+            if !named || !path.is_dir() {
+                continue;
+            }
+
+            if dir == cgroup {
+                let _ = std::fs::write(path.join("cgroup.kill"), "1");
+                std::fs::remove_dir(&path)
+            } else {
+                std::fs::remove_dir_all(&path)
+            }
+            .with_context(|| format!("Could not remove `{}`", path.display()))?;
+            reaped += 1;
+        }
+    }
+    Ok(reaped)
+}
+
+/// The work dir and cgroup of one execution, removed when dropped so that no
+/// path out of it leaks them
+struct SandboxDirs {
+    work: PathBuf,
+    cgroup: PathBuf,
+}
+
+impl Drop for SandboxDirs {
+    fn drop(&mut self) {
+        let (work, cgroup) = (self.work.clone(), self.cgroup.clone());
+        tokio::spawn(async move {
+            if let Err(e) = fs::remove_dir_all(&work).await {
+                tracing::error!("Could not remove `{}`: {e}", work.display());
+            }
+            if !cgroup.exists() {
+                return;
+            }
+
+            // a cgroup can only be removed once everything in it is gone
+            let _ = fs::write(cgroup.join("cgroup.kill"), "1").await;
+            for _ in 0..10 {
+                if fs::remove_dir(&cgroup).await.is_ok() {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            tracing::error!("Could not remove cgroup `{}`", cgroup.display());
+        });
+    }
+}
+
+impl BubblewrapBackend {
+    /// Where `path`, in the work dir of the sandbox, is on the host
+    fn host_path(&self, work: &Path, path: &str) -> eyre::Result<PathBuf> {
+        let relative = Path::new(path)
+            .strip_prefix(&self.lang.work_dir)
+            .with_context(|| format!("`{path}` is outside of the work dir"))?;
+        ensure!(
+            relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_))),
+            "`{path}` is outside of the work dir"
+        );
+        Ok(work.join(relative))
+    }
+
+    fn command(&self, dirs: &SandboxDirs, phase: Phase) -> eyre::Result<Command> {
+        let lang = &self.lang;
+        let sandbox = &self.sandbox;
+        let entrypoint = match phase {
+            Phase::Build => lang.build.as_ref().map(|b| &b.command),
+            Phase::Run => lang.entrypoint.as_ref(),
+        }
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "`{}` needs an `entrypoint` to run without Docker",
+                lang.name
+            )
+        })?;
+
+        // the shell moves itself into the cgroup before becoming bwrap, so that
+        // nothing runs outside of it
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(r#"echo $$ > "$0" && exec "$@""#)
+            .arg(dirs.cgroup.join("cgroup.procs"))
+            .arg("prlimit")
+            .arg(format!("--fsize={}", sandbox.file_size))
+            .arg(format!("--nofile={}", sandbox.open_files))
+            .arg(&self.cfg.bwrap)
+            .args(["--unshare-all", "--die-with-parent", "--new-session"])
+            .args(["--cap-drop", "ALL"])
+            .args(["--uid", &sandbox.uid.to_string()])
+            .args(["--gid", &sandbox.gid.to_string()]);
+        match &self.cfg.root {
+            Some(root) => {
+                cmd.arg("--ro-bind").arg(root.join(&lang.name)).arg("/");
+            }
+            None => {
+                for dir in ["/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc", "/opt"] {
+                    cmd.args(["--ro-bind-try", dir, dir]);
+                }
+            }
+        }
+        cmd.args(["--proc", "/proc", "--dev", "/dev"])
+            .args(["--size", &sandbox.tmpfs_size.to_string(), "--tmpfs", "/tmp"])
+            .arg("--bind")
+            .arg(&dirs.work)
+            .arg(&lang.work_dir)
+            .args(["--chdir", &lang.work_dir])
+            .arg("--clearenv");
+        for (key, value) in &self.cfg.env {
+            cmd.args(["--setenv", key, value]);
+        }
+        cmd.arg("--")
+            .args(entrypoint)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        Ok(cmd)
+    }
+
+    async fn run(&self, job: Job<'_>) -> eyre::Result<Output> {
+        let name = format!("{}{}{}", self.name_prefix, self.lang.name, Uuid::now_v7());
+        let dirs = SandboxDirs {
+            work: std::env::temp_dir().join(&name),
+            cgroup: self.cfg.cgroup.join(&name),
+        };
+
+        fs::create_dir(&dirs.work).await?;
+        for (path, content) in job.files {
+            let path = self.host_path(&dirs.work, path)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::write(path, content).await?;
+        }
+
+        let limits = job.limits;
+        fs::create_dir(&dirs.cgroup).await?;
+        // `cpu.max` is microseconds of cpu time per period of 100ms
+        for (file, value) in [
+            ("memory.max", limits.memory.to_string()),
+            ("pids.max", limits.pids.to_string()),
+            (
+                "cpu.max",
+                format!("{} 100000", (limits.cpus * 100_000.0) as u64),
+            ),
+        ] {
+            fs::write(dirs.cgroup.join(file), value)
+                .await
+                .with_context(|| {
+                    format!("Could not set `{file}` of `{}`", dirs.cgroup.display())
+                })?;
+        }
+        // only there if the kernel accounts for swap
+        let swap = dirs.cgroup.join("memory.swap.max");
+        if swap.exists() {
+            fs::write(swap, "0").await?;
+        }
+
+        let mut child = self
+            .command(&dirs, job.phase)?
+            .spawn()
+            .context("Could not start bwrap")?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let chunks = stream::select(
+            read_chunks(stdout, RawChunk::Stdout),
+            read_chunks(stderr, RawChunk::Stderr),
+        );
+        let exited = async {
+            child.wait().await?;
+            eyre::Ok(())
+        };
+        let output = collect_output(Box::pin(chunks), exited, limits, job.forward).await?;
+        if output.limit.is_some() {
+            // bwrap takes the whole namespace down with it
+            if let Err(e) = child.kill().await {
+                tracing::debug!("Could not kill sandbox {name}: {e}");
+            }
+        }
+
+        let status = child.wait().await?;
+        let exit_code = status.code().map_or(-1, |code| code as isize);
+        let oom_killed = fs::read_to_string(dirs.cgroup.join("memory.events"))
+            .await
+            .ok()
+            .and_then(|events| {
+                let count = events.lines().find_map(|l| l.strip_prefix("oom_kill "))?;
+                count.trim().parse::<u64>().ok()
+            })
+            .is_some_and(|count| count > 0);
+        let collected = match output.limit {
+            Some(_) => None,
+            None => {
+                let path = self.host_path(&dirs.work, job.collect)?;
+                read_collected(&dirs.work, &path, limits.output).await
+            }
+        };
+
+        Ok(Output {
+            exit_code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            collected,
+            limit: output.limit.or(oom_killed.then_some(Limit::Memory)),
+        })
+    }
+}
+
+impl ExecutionBackend for BubblewrapBackend {
+    fn execute<'a>(&'a self, job: Job<'a>) -> BoxFuture<'a, eyre::Result<Output>> {
+        Box::pin(self.run(job))
+    }
+}
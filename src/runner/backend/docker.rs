@@ -0,0 +1,302 @@
+use std::{
+    fs,
+    io::Read,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use docker_api::{
+    conn::TtyChunk,
+    models::{ImageBuildChunk, ImageSummary},
+    opts::{
+        ContainerCreateOpts, ImageBuildOpts, ImageFilter, ImageListOpts, NetworkCreateOpts,
+        NetworkFilter, NetworkListOpts,
+    },
+    Container, Docker,
+};
+use futures::{future::BoxFuture, stream::StreamExt, Stream, TryStreamExt};
+use uuid::Uuid;
+
+use crate::{
+    config::{DockerConfig, Limits, SandboxConfig},
+    langs::{LangInfo, Languages},
+    runner::{
+        cleanup::ContainerGuard,
+        exec::{Limit, Output},
+        pool::ContainerPool,
+        Phase,
+    },
+};
+
+use super::{collect_output, ExecutionBackend, Job, RawChunk};
+
+/// Runs every program in its own container, built from the language's Dockerfile
+pub struct DockerBackend {
+    pub docker: Docker,
+    pub image_id: String,
+    pub network_id: String,
+    pub container_name_prefix: String,
+    pub lang: LangInfo,
+    /// what pooled containers are created with, the configured limits with the
+    /// language's overrides applied
+    pub limits: Limits,
+    pub sandbox: SandboxConfig,
+    pub pool: ContainerPool,
+    pub health_check_interval: Duration,
+}
+
+/// Set up a backend for every language, in the same order
+pub async fn backends(
+    cfg: &DockerConfig,
+    docker: &Docker,
+    langs: &Languages,
+) -> eyre::Result<Vec<Arc<DockerBackend>>> {
+    let network_name = cfg.name_prefix.clone() + "network";
+
+    let filter = NetworkFilter::Name(network_name.clone());
+    let list_opts = NetworkListOpts::builder().filter([filter]);
+    let network_list = docker.networks().list(&list_opts.build()).await?;
+    assert!(
+        network_list.len() <= 1,
+        "Docker Network names should be unique"
+    );
+
+    // get the network id, building a new network if necessary
+    let network_id = match network_list.into_iter().next() {
+        Some(net) => net.id.expect("Network has an ID"),
+        None => {
+            tracing::info!("Creating new docker network: `{network_name}`");
+            let create_opts = NetworkCreateOpts::builder(network_name).internal(true);
+            let net = docker.networks().create(&create_opts.build()).await?;
+            net.id().to_string()
+        }
+    };
+
+    let b_futures = langs
+        .iter()
+        .map(|lang| DockerBackend::new(docker, cfg, lang, &network_id));
+    let backends = futures::future::join_all(b_futures)
+        .await
+        .into_iter()
+        .map(|b| b.map(Arc::new))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(backends)
+}
+
+impl DockerBackend {
+    pub async fn new(
+        docker: &Docker,
+        cfg: &DockerConfig,
+        lang: &LangInfo,
+        network_id: &str,
+    ) -> docker_api::Result<Self> {
+        let lang_name = &lang.name;
+        let image_name = cfg.name_prefix.clone() + lang_name;
+
+        let images = docker.images();
+        let filter = ImageFilter::Reference(image_name.clone(), None);
+        let list_opts = ImageListOpts::builder().filter([filter]);
+        let image_list = images.list(&list_opts.build()).await?;
+        assert!(image_list.len() <= 1, "Docker Image names should be unique");
+
+        // get the image id, building a new image if necessary
+        let path = lang.dir.to_string_lossy().into_owned();
+        let age = fs::metadata(lang.dockerfile_path())
+            .expect("Dockerfile exists")
+            .modified()
+            .unwrap();
+        let image_age =
+            |s: &ImageSummary| SystemTime::UNIX_EPOCH + Duration::from_secs(s.created as u64);
+
+        let image_id = match image_list.into_iter().next() {
+            Some(s) if image_age(&s) > age => s.id,
+            _ => {
+                tracing::info!("Building new docker image: `{image_name}`");
+                let build_opts = ImageBuildOpts::builder(path)
+                    .tag(&image_name)
+                    .labels([(cfg.image_label.clone(), lang_name.to_string())]);
+                let stream = images.build(&build_opts.build());
+                let mut id = String::new();
+                consume_image_build_stream(stream, &mut id).await;
+                assert!(!id.is_empty(), "Expected `Aux` from image build stream");
+
+                id
+            }
+        };
+
+        Ok(Self {
+            docker: docker.clone(),
+            image_id,
+            network_id: network_id.to_owned(),
+            container_name_prefix: cfg.container_name_prefix.clone(),
+            lang: lang.clone(),
+            limits: cfg.limits.apply(&lang.limits),
+            sandbox: cfg.sandbox.clone(),
+            pool: ContainerPool::new(lang.pool_size.unwrap_or(cfg.pool.max_idle)),
+            health_check_interval: cfg.pool.health_check_interval,
+        })
+    }
+
+    pub(in crate::runner) async fn create_container(
+        &self,
+        limits: &Limits,
+        phase: Phase,
+    ) -> docker_api::Result<Container> {
+        let uuid = Uuid::now_v7();
+        let sandbox = &self.sandbox;
+
+        let mut security_options = vec!["no-new-privileges".to_string()];
+        if let Some(profile) = &self.lang.seccomp {
+            security_options.push(format!("seccomp={profile}"));
+        }
+
+        let entrypoint = match phase {
+            Phase::Build => self.lang.build.as_ref().map(|b| &b.command),
+            Phase::Run => self.lang.entrypoint.as_ref(),
+        };
+
+        let mut opts = ContainerCreateOpts::builder()
+            .image(&self.image_id)
+            .cpus(limits.cpus)
+            .memory(limits.memory)
+            .pids_limit(limits.pids)
+            .network_mode(&self.network_id)
+            .privileged(false)
+            .user(format!("{}:{}", sandbox.uid, sandbox.gid))
+            .capabilities_drop(["ALL"])
+            .security_options(security_options)
+            .readonly_rootfs(true)
+            // `docker cp` can not write into a tmpfs, so the files we copy go into an
+            // anonymous volume instead
+            .volumes([&self.lang.work_dir])
+            .working_dir(&self.lang.work_dir)
+            .tmpfs([(
+                "/tmp",
+                format!("rw,noexec,nosuid,size={}", sandbox.tmpfs_size),
+            )])
+            .ulimits([
+                ("fsize", sandbox.file_size, sandbox.file_size),
+                ("nofile", sandbox.open_files, sandbox.open_files),
+            ])
+            .name(self.container_name_prefix.clone() + &self.lang.name + &uuid.to_string()); // container names must be unique
+        if let Some(entrypoint) = entrypoint {
+            opts = opts.entrypoint(entrypoint.clone());
+        }
+        let container = self.docker.containers().create(&opts.build()).await?;
+
+        Ok(container)
+    }
+
+    async fn run(&self, job: Job<'_>) -> eyre::Result<Output> {
+        // pooled containers were created to run, with the backend's limits
+        let container_limits = |l: &Limits| (l.cpus, l.memory, l.pids);
+        let pooled = job.phase == Phase::Run
+            && container_limits(job.limits) == container_limits(&self.limits);
+        let container = match pooled.then(|| self.pool.take()).flatten() {
+            Some(container) => container,
+            None => self.create_container(job.limits, job.phase).await?,
+        };
+        let container = ContainerGuard::new(container, self.pool.in_use.clone());
+
+        for (path, content) in job.files {
+            container.copy_file_into(path, content).await?;
+        }
+
+        let (out_stream, _) = container.attach().await?.split();
+        container.start().await?;
+
+        let chunks = out_stream
+            .map_err(eyre::Report::from)
+            .try_filter_map(|chunk| async {
+                Ok(match chunk {
+                    TtyChunk::StdIn(_) => None, // ignore
+                    TtyChunk::StdOut(data) => Some(RawChunk::Stdout(data)),
+                    TtyChunk::StdErr(data) => Some(RawChunk::Stderr(data)),
+                })
+            });
+        let exited = async {
+            container.wait().await?;
+            eyre::Ok(())
+        };
+        let output = collect_output(Box::pin(chunks), exited, job.limits, job.forward).await?;
+        if output.limit.is_some() {
+            // the container may have exited in the meantime
+            if let Err(e) = container.kill(None).await {
+                tracing::debug!("Could not kill container {}: {e}", container.id());
+            }
+        }
+
+        let state = container.inspect().await?.state;
+        let exit_code = state.as_ref().and_then(|s| s.exit_code).unwrap_or(-1);
+        let oom_killed = state.and_then(|s| s.oom_killed).unwrap_or(false);
+        let collected = match output.limit {
+            Some(_) => None,
+            None => copy_file_from(&container, job.collect, job.limits.output).await,
+        };
+
+        Ok(Output {
+            exit_code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            collected,
+            limit: output.limit.or(oom_killed.then_some(Limit::Memory)),
+        })
+    }
+}
+
+impl ExecutionBackend for DockerBackend {
+    fn execute<'a>(&'a self, job: Job<'a>) -> BoxFuture<'a, eyre::Result<Output>> {
+        Box::pin(self.run(job))
+    }
+
+    fn maintain(self: Arc<Self>) -> BoxFuture<'static, ()> {
+        Box::pin(self.maintain_pool())
+    }
+}
+
+/// Headers and padding a tarball of a single file takes on top of the file
+const TAR_OVERHEAD: u64 = 16 << 10;
+
+/// Copy a single file out of a stopped container. `None` if it does not exist,
+/// or is anything but a regular file of at most `max` bytes.
+async fn copy_file_from(container: &Container, path: &str, max: u64) -> Option<Vec<u8>> {
+    // docker answers with a tarball, or an error if there is no such file
+    let mut chunks = container.copy_from(Path::new(path));
+    let mut tarball = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        tarball.extend(chunk.ok()?);
+        if tarball.len() as u64 > max + TAR_OVERHEAD {
+            return None;
+        }
+    }
+
+    let mut archive = tar::Archive::new(tarball.as_slice());
+    let mut entry = archive.entries().ok()?.next()?.ok()?;
+    if !entry.header().entry_type().is_file() || entry.size() > max {
+        return None;
+    }
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content).ok()?;
+    Some(content)
+}
+
+async fn consume_image_build_stream(
+    mut stream: impl Stream<Item = docker_api::Result<ImageBuildChunk>> + Unpin,
+    id: &mut String,
+) {
+    while let Some(s) = stream.next().await {
+        match s {
+            Ok(ImageBuildChunk::Update { stream }) => eprintln!("{}", stream.trim()),
+            Ok(ImageBuildChunk::Error { error, .. }) => tracing::error!("{error}"),
+            Ok(ImageBuildChunk::Digest { aux }) => {
+                eprintln!("{}", aux.id);
+                *id = aux.id;
+            }
+            Ok(ImageBuildChunk::PullStatus { progress, .. }) => {
+                eprintln!("{}", progress.unwrap_or_default())
+            }
+            Err(e) => tracing::error!("{e}"),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Backends for tests, which need neither docker nor a `config.yaml`
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use eyre::Context;
+use futures::{future::BoxFuture, stream};
+use tokio::{fs, process::Command};
+use uuid::Uuid;
+
+use crate::{
+    app::Templates,
+    config::Limits,
+    langs::{LangInfo, Languages},
+    runner::{
+        build::CompileCache,
+        exec::{Files, Limit, Output},
+        Phase, Runner, RunnerRegistry,
+    },
+};
+
+use super::{collect_output, read_chunks, read_collected, ExecutionBackend, Job, RawChunk};
+
+pub fn limits() -> Limits {
+    Limits {
+        cpus: 1.0,
+        memory: 64 << 20,
+        pids: 64,
+        timeout: Duration::from_secs(10),
+        output: 1 << 20,
+    }
+}
+
+/// A runner for every language, with the backend `backend` makes for it
+pub fn registry(
+    backend: impl Fn(&LangInfo) -> Arc<dyn ExecutionBackend>,
+) -> (Templates, RunnerRegistry) {
+    let langs = Languages::new().unwrap();
+    let mut templates = Templates::new(handlebars::Handlebars::new());
+    let registry = langs
+        .iter()
+        .map(|lang| {
+            templates.register_lang(lang).unwrap();
+            let runner = Runner {
+                lang: lang.clone(),
+                limits: limits().apply(&lang.limits),
+                compile_cache: CompileCache::new(4),
+                backend: backend(lang),
+            };
+            (lang.name.clone(), Arc::new(runner))
+        })
+        .collect();
+    (templates, registry)
+}
+
+/// Runs the language's commands straight on the host, in a temporary work dir.
+/// Only the timeout and output limits are enforced, and the toolchain has to be
+/// installed.
+pub struct HostBackend {
+    pub lang: LangInfo,
+}
+
+/// A [`HostBackend`] for `lang`, to pass to [`registry`]
+pub fn host(lang: &LangInfo) -> Arc<dyn ExecutionBackend> {
+    Arc::new(HostBackend { lang: lang.clone() })
+}
+
+impl HostBackend {
+    fn host_path(&self, work: &Path, path: &str) -> eyre::Result<PathBuf> {
+        Ok(work.join(Path::new(path).strip_prefix(&self.lang.work_dir)?))
+    }
+
+    async fn run(&self, job: Job<'_>) -> eyre::Result<Output> {
+        let work = std::env::temp_dir().join(format!("amplitude-test-{}", Uuid::now_v7()));
+        fs::create_dir(&work).await?;
+        let output = self.run_in(&work, job).await;
+        fs::remove_dir_all(&work).await?;
+        output
+    }
+
+    async fn run_in(&self, work: &Path, job: Job<'_>) -> eyre::Result<Output> {
+        for (path, content) in job.files {
+            fs::write(self.host_path(work, path)?, content).await?;
+        }
+
+        let command = match job.phase {
+            Phase::Build => self.lang.build.as_ref().map(|b| &b.command),
+            Phase::Run => self.lang.entrypoint.as_ref(),
+        };
+        let Some([program, args @ ..]) = command.map(Vec::as_slice) else {
+            eyre::bail!(
+                "`{}` has nothing to run for {:?}",
+                self.lang.name,
+                job.phase
+            );
+        };
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(work)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Could not run `{program}`"))?;
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let chunks = stream::select(
+            read_chunks(stdout, RawChunk::Stdout),
+            read_chunks(stderr, RawChunk::Stderr),
+        );
+        let exited = async {
+            child.wait().await?;
+            eyre::Ok(())
+        };
+        let output = collect_output(Box::pin(chunks), exited, job.limits, job.forward).await?;
+        if output.limit.is_some() {
+            let _ = child.kill().await;
+        }
+
+        let status = child.wait().await?;
+        let collected = match output.limit {
+            Some(_) => None,
+            None => {
+                let path = self.host_path(work, job.collect)?;
+                read_collected(work, &path, job.limits.output).await
+            }
+        };
+        Ok(Output {
+            exit_code: status.code().map_or(-1, |code| code as isize),
+            stdout: output.stdout,
+            stderr: output.stderr,
+            collected,
+            limit: output.limit,
+        })
+    }
+}
+
+impl ExecutionBackend for HostBackend {
+    fn execute<'a>(&'a self, job: Job<'a>) -> BoxFuture<'a, eyre::Result<Output>> {
+        Box::pin(self.run(job))
+    }
+}
+
+/// Answers every job with the next of its canned outputs, without running
+/// anything, and remembers the jobs it was given
+#[derive(Default)]
+pub struct ReplayBackend {
+    outputs: Mutex<VecDeque<Output>>,
+    pub jobs: Mutex<Vec<(Phase, Files)>>,
+}
+
+impl ReplayBackend {
+    pub fn new(outputs: impl IntoIterator<Item = Output>) -> Arc<Self> {
+        Arc::new(Self {
+            outputs: Mutex::new(outputs.into_iter().collect()),
+            jobs: Default::default(),
+        })
+    }
+}
+
+impl ExecutionBackend for ReplayBackend {
+    fn execute<'a>(&'a self, job: Job<'a>) -> BoxFuture<'a, eyre::Result<Output>> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .push((job.phase, job.files.clone()));
+        let output = self.outputs.lock().unwrap().pop_front();
+        Box::pin(async move { output.ok_or_else(|| eyre::eyre!("No outputs left to replay")) })
+    }
+}
+
+/// A program that exited with `exit_code`, having written `collected`
+pub fn exited(exit_code: isize, collected: Option<&[u8]>) -> Output {
+    Output {
+        exit_code,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        collected: collected.map(<[u8]>::to_vec),
+        limit: None,
+    }
+}
+
+/// A program that was killed for exceeding `limit`
+pub fn killed(limit: Limit) -> Output {
+    Output {
+        exit_code: 137,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        collected: None,
+        limit: Some(limit),
+    }
+}
@@ -0,0 +1,221 @@
+//! Where programs actually run. A [`Runner`](super::Runner) decides what to run
+//! and makes sense of the results, and its backend, set up for the runner's
+//! language, runs it in a sandbox.
+
+use std::{future::Future, os::unix::fs::MetadataExt, path::Path, sync::Arc};
+
+use futures::{future::BoxFuture, stream, Stream, TryStreamExt};
+use tokio::{
+    fs,
+    io::{AsyncRead, AsyncReadExt},
+};
+
+use crate::config::Limits;
+
+use super::{
+    exec::{Files, Limit, Output, OutputChunk, OutputSender},
+    Phase,
+};
+
+pub mod bubblewrap;
+pub mod docker;
+#[cfg(test)]
+pub mod fake;
+
+/// One program to run, from copying its files in to collecting what it wrote
+pub struct Job<'a> {
+    pub limits: &'a Limits,
+    pub phase: Phase,
+    /// absolute paths in the work dir
+    pub files: &'a Files,
+    /// the file to read back once the program exits, if it was not killed. It
+    /// is not read if it is larger than the output limit.
+    pub collect: &'a str,
+    pub forward: Option<&'a OutputSender>,
+}
+
+pub trait ExecutionBackend: Send + Sync {
+    /// Run `job` in a fresh sandbox, which is cleaned up afterwards
+    fn execute<'a>(&'a self, job: Job<'a>) -> BoxFuture<'a, eyre::Result<Output>>;
+
+    /// Upkeep that runs for as long as the server does, like filling a pool of
+    /// containers. Spawned once for every runner.
+    fn maintain(self: Arc<Self>) -> BoxFuture<'static, ()> {
+        Box::pin(async {})
+    }
+}
+
+/// Output of a sandboxed program, as read from its pipes
+pub enum RawChunk {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+}
+
+pub struct Collected {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// set if the program has to be killed
+    pub limit: Option<Limit>,
+}
+
+/// Read `chunks` until they end and the program has `exited`, forwarding them
+/// as they arrive. Stops early once the program runs out of time or prints
+/// more than it may, in which case the caller has to kill it.
+pub async fn collect_output(
+    mut chunks: impl Stream<Item = eyre::Result<RawChunk>> + Unpin,
+    exited: impl Future<Output = eyre::Result<()>>,
+    limits: &Limits,
+    forward: Option<&OutputSender>,
+) -> eyre::Result<Collected> {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let max_output = limits.output as usize;
+
+    let collect = async {
+        while let Some(chunk) = chunks.try_next().await? {
+            // nothing past the limit is kept or forwarded
+            let remaining = max_output - (stdout.len() + stderr.len());
+            let (out, data, chunk): (_, _, fn(String) -> OutputChunk) = match chunk {
+                RawChunk::Stdout(data) => (&mut stdout, data, OutputChunk::Stdout),
+                RawChunk::Stderr(data) => (&mut stderr, data, OutputChunk::Stderr),
+            };
+            let exceeded = data.len() > remaining;
+            let data = &data[..data.len().min(remaining)];
+            if let Some(forward) = forward {
+                // nobody may be listening anymore, which is fine
+                let _ = forward.unbounded_send(chunk(String::from_utf8_lossy(data).into()));
+            }
+            out.extend_from_slice(data);
+            if exceeded {
+                return Ok(Some(Limit::Output));
+            }
+        }
+
+        exited.await?;
+        eyre::Ok(None)
+    };
+    let limit = match tokio::time::timeout(limits.timeout, collect).await {
+        Ok(limit) => limit?,
+        Err(_) => Some(Limit::Time),
+    };
+
+    Ok(Collected {
+        stdout,
+        stderr,
+        limit,
+    })
+}
+
+/// Read a pipe as chunks of output, as they arrive
+fn read_chunks(
+    pipe: impl AsyncRead + Unpin,
+    chunk: fn(Vec<u8>) -> RawChunk,
+) -> impl Stream<Item = eyre::Result<RawChunk>> {
+    stream::try_unfold(pipe, move |mut pipe| async move {
+        let mut buf = vec![0; 8192];
+        let read = pipe.read(&mut buf).await?;
+        buf.truncate(read);
+        eyre::Ok((read > 0).then(|| (chunk(buf), pipe)))
+    })
+}
+
+/// Read back the file a program left at `path`, in its `work` dir on the host.
+/// The program controls everything in there, so this is `None` unless `path`
+/// is a regular file of at most `max` bytes, reached without following links.
+async fn read_collected(work: &Path, path: &Path, max: u64) -> Option<Vec<u8>> {
+    let relative = path.strip_prefix(work).ok()?;
+    let mut dir = work.to_path_buf();
+    for component in relative.parent()?.components() {
+        dir.push(component);
+        // `symlink_metadata` does not follow links, so they are not dirs
+        if !fs::symlink_metadata(&dir).await.ok()?.is_dir() {
+            return None;
+        }
+    }
+
+    // without `O_NONBLOCK`, opening a fifo would wait for a writer
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+        .open(path)
+        .await
+        .ok()?;
+    let metadata = file.metadata().await.ok()?;
+    if !metadata.is_file() || metadata.nlink() != 1 || metadata.len() > max {
+        return None;
+    }
+
+    let mut content = Vec::new();
+    file.take(max + 1).read_to_end(&mut content).await.ok()?;
+    (content.len() as u64 <= max).then_some(content)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{os::unix::fs::symlink, time::Duration};
+
+    use futures::{channel::mpsc, future, stream, StreamExt};
+
+    use crate::{
+        config::Limits,
+        runner::exec::{Limit, OutputChunk},
+    };
+
+    use super::{collect_output, read_collected, RawChunk};
+
+    #[tokio::test]
+    async fn test_collect_output() {
+        let limits = Limits {
+            output: 8,
+            timeout: Duration::from_secs(1),
+            ..super::fake::limits()
+        };
+        let chunks = stream::iter([
+            Ok(RawChunk::Stdout(b"abc".to_vec())),
+            Ok(RawChunk::Stderr(b"0123456789".to_vec())),
+            Ok(RawChunk::Stdout(b"never read".to_vec())),
+        ]);
+        let (tx, rx) = mpsc::unbounded();
+
+        let collected = collect_output(chunks, future::ok(()), &limits, Some(&tx))
+            .await
+            .unwrap();
+        assert_eq!(collected.limit, Some(Limit::Output));
+        assert_eq!(collected.stdout, b"abc");
+        assert_eq!(collected.stderr, b"01234");
+
+        drop(tx);
+        let forwarded: Vec<OutputChunk> = rx.collect().await;
+        assert_eq!(
+            forwarded,
+            [
+                OutputChunk::Stdout("abc".to_string()),
+                OutputChunk::Stderr("01234".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_collected() {
+        let work = std::env::temp_dir().join(format!("amplitude-test-{}", uuid::Uuid::now_v7()));
+        std::fs::create_dir_all(work.join("dir")).unwrap();
+        std::fs::write(work.join("results.json"), "[]").unwrap();
+        std::fs::write(work.join("dir/big.json"), "[1, 2, 3]").unwrap();
+        symlink(work.join("results.json"), work.join("link.json")).unwrap();
+        symlink(work.join("dir"), work.join("linked")).unwrap();
+        symlink("/dev/zero", work.join("zero")).unwrap();
+
+        let read = |path: &str| {
+            let (work, path) = (&work, work.join(path));
+            async move { read_collected(work, &path, 4).await }
+        };
+        assert_eq!(read("results.json").await, Some(b"[]".to_vec()));
+        assert_eq!(read("dir/big.json").await, None);
+        assert_eq!(read("link.json").await, None);
+        assert_eq!(read("linked/big.json").await, None);
+        assert_eq!(read("zero").await, None);
+        assert_eq!(read("missing.json").await, None);
+
+        std::fs::remove_dir_all(&work).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::VecDeque,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use super::{
+    exec::{Files, Output, OutputSender},
+    Phase, Runner,
+};
+
+/// Artifacts of recent builds, looked up by a hash of their sources. The
+/// sources are kept too, so that a collision is a miss rather than someone
+/// else's program.
+pub struct CompileCache {
+    entries: Mutex<VecDeque<CacheEntry>>,
+    capacity: usize,
+}
+
+struct CacheEntry {
+    key: u64,
+    sources: Files,
+    artifact: Arc<[u8]>,
+}
+
+impl CompileCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    fn get(&self, sources: &Files) -> Option<Arc<[u8]>> {
+        let key = hash_sources(sources);
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .find(|e| e.key == key && e.sources == *sources)
+            .map(|e| e.artifact.clone())
+    }
+
+    /// Add an artifact, evicting the oldest one if the cache is full
+    fn insert(&self, sources: Files, artifact: Arc<[u8]>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(CacheEntry {
+            key: hash_sources(&sources),
+            sources,
+            artifact,
+        });
+    }
+}
+
+fn hash_sources(sources: &Files) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    sources.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Runner {
+    /// Compile `sources` if the language needs it, returning the files to run,
+    /// or the output of the failed build
+    pub(super) async fn build(
+        &self,
+        sources: Files,
+        forward: Option<&OutputSender>,
+    ) -> eyre::Result<Result<Files, Output>> {
+        let Some(build) = &self.lang.build else {
+            return Ok(Ok(sources));
+        };
+
+        let artifact = match self.compile_cache.get(&sources) {
+            Some(artifact) => artifact,
+            None => {
+                let limits = self.limits.apply(&build.limits);
+                let mut output = self
+                    .execute(&limits, Phase::Build, &sources, forward)
+                    .await?;
+                let artifact: Arc<[u8]> = match output.collected.take() {
+                    Some(artifact) if output.exit_code == 0 && output.limit.is_none() => {
+                        artifact.into()
+                    }
+                    _ => return Ok(Err(output)),
+                };
+                self.compile_cache.insert(sources, artifact.clone());
+                artifact
+            }
+        };
+
+        Ok(Ok(vec![(self.lang.work_path(&build.artifact), artifact)]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compile_cache() {
+        let cache = CompileCache::new(2);
+        let artifact = |b: u8| -> Arc<[u8]> { Arc::from([b]) };
+        let sources = |code: &str| vec![("main.c".to_string(), Arc::from(code.as_bytes()))];
+
+        cache.insert(sources("1"), artifact(1));
+        cache.insert(sources("2"), artifact(2));
+        assert_eq!(cache.get(&sources("1")), Some(artifact(1)));
+
+        cache.insert(sources("3"), artifact(3));
+        assert_eq!(cache.get(&sources("1")), None);
+        assert_eq!(cache.get(&sources("3")), Some(artifact(3)));
+
+        // a colliding hash is not enough for a hit
+        cache.entries.lock().unwrap()[0].key = hash_sources(&sources("4"));
+        assert_eq!(cache.get(&sources("4")), None);
+
+        assert_eq!(hash_sources(&sources("a")), hash_sources(&sources("a")));
+        assert_ne!(hash_sources(&sources("a")), hash_sources(&sources("b")));
+    }
+}
@@ -0,0 +1,258 @@
+//! Checkers grade solutions with a program written by the exercise's author,
+//! for exercises where more than one output is right
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    app::Templates,
+    routes::exec::RunRequest,
+    runner::exec::{Limit, RunnerResult, RunnerSuccess},
+};
+
+use super::Runner;
+
+/// A script that decides whether the output of a case is right, in a language
+/// with a `checker.hbs`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Checker {
+    pub language: String,
+    pub content: String,
+}
+
+/// What a checker decided about one case
+#[derive(Debug, Deserialize)]
+struct Verdict {
+    passed: bool,
+    #[serde(default)]
+    score: Option<f64>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// A case, as the checker's harness reads it from [`CASES_FILE`]
+#[derive(Serialize)]
+struct CheckedCase<'a> {
+    input: &'a [Value],
+    expected: &'a Value,
+    actual: &'a Value,
+}
+
+/// Where the cases to check are copied to in the work dir
+pub(crate) const CASES_FILE: &str = "cases.json";
+
+impl Runner {
+    /// Grade the cases of `success`, the result of running `req`, with
+    /// `checker`. Cases without an output of the right type fail without being
+    /// checked.
+    pub async fn run_checker(
+        &self,
+        templates: &Templates,
+        checker: &Checker,
+        req: &RunRequest,
+        mut success: RunnerSuccess,
+    ) -> eyre::Result<RunnerResult> {
+        let mut checked = Vec::new();
+        let mut cases = Vec::new();
+        for (i, case) in success.cases.iter_mut().enumerate() {
+            case.passed = false;
+            let Some(actual) = &case.actual else {
+                continue;
+            };
+            if !req.output.check(actual) {
+                case.message = Some(format!("Expected output to be `{}`", req.output));
+                continue;
+            }
+            checked.push(i);
+            cases.push(CheckedCase {
+                input: &req.cases[i].input,
+                expected: &req.cases[i].output,
+                actual,
+            });
+        }
+
+        if !cases.is_empty() {
+            let harness = templates.render_checker(&self.lang)?;
+            let mut files = self.sources(harness, &self.lang.sources.checker, &checker.content);
+            files.push((
+                self.lang.work_path(CASES_FILE),
+                serde_json::to_vec(&cases)?.into(),
+            ));
+
+            let output = match self.build_and_run(&self.limits, files, None).await? {
+                Ok(output) => output,
+                Err(build) => {
+                    let message = "Checker could not be built".to_string();
+                    return Ok(RunnerResult::CheckerError(
+                        build.into_checker_error(message),
+                    ));
+                }
+            };
+            if let Some(limit) = output.limit {
+                let limit = match limit {
+                    Limit::Time => "time",
+                    Limit::Memory => "memory",
+                    Limit::Output => "output",
+                };
+                let message = format!("Checker exceeded its {limit} limit");
+                return Ok(RunnerResult::CheckerError(
+                    output.into_checker_error(message),
+                ));
+            }
+            let results = match output.collected.as_deref() {
+                Some(results) if output.exit_code == 0 => results,
+                _ => {
+                    let message = format!("Checker exited with code {}", output.exit_code);
+                    return Ok(RunnerResult::CheckerError(
+                        output.into_checker_error(message),
+                    ));
+                }
+            };
+
+            let verdicts = match parse_verdicts(results, cases.len()) {
+                Ok(verdicts) => verdicts,
+                Err(message) => {
+                    return Ok(RunnerResult::CheckerError(
+                        output.into_checker_error(message),
+                    ))
+                }
+            };
+            for (i, verdict) in checked.into_iter().zip(verdicts) {
+                let case = &mut success.cases[i];
+                case.passed = verdict.passed;
+                case.score = verdict.score;
+                case.message = verdict.message;
+            }
+        }
+
+        success.passed = success.cases.iter().filter(|c| c.passed).count();
+        Ok(RunnerResult::Success(success))
+    }
+}
+
+/// Read what the checker decided about each of `count` cases, or why that is
+/// not possible, which is the fault of the checker
+fn parse_verdicts(results: &[u8], count: usize) -> Result<Vec<Verdict>, String> {
+    let verdicts: Vec<Verdict> =
+        serde_json::from_slice(results).map_err(|e| format!("Invalid verdicts: {e}"))?;
+    if verdicts.len() != count {
+        return Err(format!(
+            "Expected {count} verdicts from checker, got {}",
+            verdicts.len()
+        ));
+    }
+    if let Some(score) = verdicts
+        .iter()
+        .filter_map(|v| v.score)
+        .find(|score| !(0.0..=1.0).contains(score))
+    {
+        return Err(format!("Scores must be between 0 and 1, got {score}"));
+    }
+    Ok(verdicts)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        routes::exec::{RunRequest, Type},
+        runner::{
+            backend::fake,
+            exec::{GeneratorCase, RunnerResult},
+        },
+    };
+
+    use super::Checker;
+
+    /// Any pair of numbers that adds up to the input is a right answer
+    const CHECKER: &str = "def check(input, expected, actual):              \n\
+                           \ta, b = actual                                    \n\
+                           \tif a + b != input[0]:                            \n\
+                           \t\treturn {'passed': False, 'message': 'wrong sum'}\n\
+                           \treturn {'passed': True, 'score': 0.5 if a == b else 1}\n";
+
+    fn request(solution: &str, checker: &str) -> RunRequest {
+        let cases: Vec<GeneratorCase> = serde_json::from_value(json!([
+            { "input": [4], "output": [1, 3] },
+            { "input": [5], "output": [2, 3] },
+            { "input": [6], "output": [1, 5] },
+        ]))
+        .unwrap();
+        RunRequest {
+            language: "python".to_string(),
+            content: solution.to_string(),
+            function_name: "split".to_string(),
+            inputs: vec![Type::Int],
+            output: "int[2]".parse().unwrap(),
+            cases,
+            comparison: Default::default(),
+            checker: Some(Checker {
+                language: "python".to_string(),
+                content: checker.to_string(),
+            }),
+            limits: Default::default(),
+        }
+    }
+
+    async fn run(req: &RunRequest) -> RunnerResult {
+        let (templates, reg) = fake::registry(fake::host);
+        let RunnerResult::Success(success) = reg[&req.language]
+            .run_solution(&templates, req, None)
+            .await
+            .unwrap()
+        else {
+            panic!()
+        };
+        let checker = req.checker.as_ref().unwrap();
+        reg[&checker.language]
+            .run_checker(&templates, checker, req, success)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_checker() {
+        let solution = "def split(n):                    \n\
+                        \tif n == 6:                     \n\
+                        \t\treturn [1]                   \n\
+                        \treturn [n // 2, n // 2]         \n";
+
+        let RunnerResult::Success(output) = run(&request(solution, CHECKER)).await else {
+            panic!()
+        };
+
+        assert_eq!(output.passed, 1);
+        assert!(output.cases[0].passed);
+        assert_eq!(output.cases[0].score, Some(0.5));
+        assert!(!output.cases[1].passed);
+        assert_eq!(output.cases[1].message.as_deref(), Some("wrong sum"));
+        // not of type `int[2]`, so never checked
+        assert!(!output.cases[2].passed);
+        assert_eq!(
+            output.cases[2].message.as_deref(),
+            Some("Expected output to be `int[2]`")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checker_error() {
+        let solution = "def split(n):     \n\
+                        \treturn [n, 0]   \n";
+        let checker = "def check(input, expected, actual):\n\
+                       \treturn {'passed': True, 'score': 2}\n";
+
+        let RunnerResult::CheckerError(error) = run(&request(solution, checker)).await else {
+            panic!()
+        };
+        assert_eq!(error.message, "Scores must be between 0 and 1, got 2");
+
+        let checker = "def check(input, expected, actual):\n\
+                       \traise ValueError('oops')         \n";
+        let RunnerResult::CheckerError(error) = run(&request(solution, checker)).await else {
+            panic!()
+        };
+        assert_eq!(error.message, "Checker exited with code 1");
+        assert!(error.stderr.contains("ValueError: oops"));
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    ops::Deref,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use docker_api::{
+    opts::{ContainerFilter, ContainerListOpts, ContainerRemoveOpts},
+    Container, Docker,
+};
+
+use super::{backend::docker::DockerBackend, pool::InUse};
+
+/// Removes its container when dropped, so that no path out of an execution,
+/// including errors and cancelled requests, leaks one. Until then the
+/// container is marked as in use, so that the reaper leaves it alone.
+pub struct ContainerGuard(Option<Container>, InUse);
+
+impl ContainerGuard {
+    pub fn new(container: Container, in_use: InUse) -> Self {
+        in_use.lock().unwrap().insert(container.id().to_string());
+        Self(Some(container), in_use)
+    }
+}
+
+impl Deref for ContainerGuard {
+    type Target = Container;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref().expect("container is only taken on drop")
+    }
+}
+
+impl Drop for ContainerGuard {
+    fn drop(&mut self) {
+        if let Some(container) = self.0.take() {
+            let in_use = self.1.clone();
+            tokio::spawn(async move {
+                let id = container.id().to_string();
+                remove_container(container).await;
+                in_use.lock().unwrap().remove(&id);
+            });
+        }
+    }
+}
+
+/// Force remove a container along with its anonymous `/runner` volume
+pub async fn remove_container(container: Container) {
+    let opts = ContainerRemoveOpts::builder().force(true).volumes(true);
+    if let Err(e) = container.remove(&opts.build()).await {
+        tracing::error!("Could not remove container {}: {e}", container.id());
+    }
+}
+
+/// Remove containers named with `prefix` that are older than `min_age` and
+/// are neither waiting in the pool of one of `backends` nor in use. These are left behind when
+/// the server crashes or is killed in the middle of an execution.
+pub async fn reap_containers(
+    docker: &Docker,
+    prefix: &str,
+    min_age: Duration,
+    backends: &[Arc<DockerBackend>],
+) -> docker_api::Result<usize> {
+    // docker matches names as regexes against `/{name}`
+    let filter = ContainerFilter::Name(format!("^/{prefix}"));
+    let list_opts = ContainerListOpts::builder().all(true).filter([filter]);
+    let containers = docker.containers().list(&list_opts.build()).await?;
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+
+    let mut reaped = 0;
+    for summary in containers {
+        let Some(id) = summary.id else { continue };
+        let named = summary
+            .names
+            .unwrap_or_default()
+            .iter()
+            .any(|n| n.trim_start_matches('/').starts_with(prefix));
+        let age = now - summary.created.unwrap_or(now);
+        let owned = backends.iter().any(|b| b.pool.contains(&id));
+        if !named || age < min_age.as_secs() as i64 || owned {
+            continue;
+        }
+
+        remove_container(docker.containers().get(id)).await;
+        reaped += 1;
+    }
+
+    Ok(reaped)
+}
@@ -0,0 +1,291 @@
+//! How the output of a solution is compared to the expected output of a case
+
+use std::{borrow::Cow, cmp::Ordering};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::routes::exec::Type;
+
+/// How the elements of arrays are compared, at every level of the output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrayOrder {
+    /// Element by element
+    #[default]
+    Ordered,
+    /// In any order, with every element matched exactly once
+    Unordered,
+    /// In any order, ignoring duplicates
+    Set,
+    /// Element by element, after sorting both arrays
+    Sorted,
+}
+
+/// The options of an exercise for comparing outputs. The default is an exact
+/// comparison.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Comparison {
+    /// how far floats may be from the expected value
+    pub absolute_epsilon: f64,
+    /// how far floats may be from the expected value, relative to its magnitude
+    pub relative_epsilon: f64,
+    pub arrays: ArrayOrder,
+    pub ignore_case: bool,
+    /// ignore leading and trailing whitespace, and the length of whitespace runs
+    pub ignore_whitespace: bool,
+}
+
+impl Comparison {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let valid = |e: f64| e.is_finite() && e >= 0.0;
+        if !valid(self.absolute_epsilon) || !valid(self.relative_epsilon) {
+            return Err("Epsilons must be non-negative numbers");
+        }
+        Ok(())
+    }
+
+    /// Whether `actual` is close enough to `expected`, which are both of type `ty`
+    pub fn matches(&self, ty: &Type, expected: &Value, actual: &Value) -> bool {
+        match (ty, expected, actual) {
+            (Type::Float, Value::Number(e), Value::Number(a)) => {
+                let (Some(e), Some(a)) = (e.as_f64(), a.as_f64()) else {
+                    return false;
+                };
+                let diff = (e - a).abs();
+                e == a
+                    || diff <= self.absolute_epsilon
+                    || diff <= self.relative_epsilon * e.abs().max(a.abs())
+            }
+            (Type::String, Value::String(e), Value::String(a)) => {
+                self.normalize(e) == self.normalize(a)
+            }
+            (Type::Array(elem, _), Value::Array(e), Value::Array(a)) => {
+                self.matches_array(elem, e, a)
+            }
+            (Type::Map(value), Value::Object(e), Value::Object(a)) => {
+                e.len() == a.len()
+                    && e.iter()
+                        .all(|(k, e)| a.get(k).is_some_and(|a| self.matches(value, e, a)))
+            }
+            _ => expected == actual,
+        }
+    }
+
+    fn matches_array(&self, elem: &Type, expected: &[Value], actual: &[Value]) -> bool {
+        let matches = |e, a| self.matches(elem, e, a);
+        match self.arrays {
+            ArrayOrder::Ordered => {
+                expected.len() == actual.len()
+                    && expected.iter().zip(actual).all(|(e, a)| matches(e, a))
+            }
+            ArrayOrder::Unordered => {
+                if expected.len() != actual.len() {
+                    return false;
+                }
+                // with epsilons, matching is not transitive, so this greedy pairing
+                // can miss a valid one in rare cases
+                let mut unmatched: Vec<&Value> = actual.iter().collect();
+                expected
+                    .iter()
+                    .all(|e| match unmatched.iter().position(|a| matches(e, a)) {
+                        Some(i) => {
+                            unmatched.swap_remove(i);
+                            true
+                        }
+                        None => false,
+                    })
+            }
+            ArrayOrder::Set => {
+                expected
+                    .iter()
+                    .all(|e| actual.iter().any(|a| matches(e, a)))
+                    && actual
+                        .iter()
+                        .all(|a| expected.iter().any(|e| matches(e, a)))
+            }
+            ArrayOrder::Sorted => {
+                if expected.len() != actual.len() {
+                    return false;
+                }
+                let mut expected: Vec<&Value> = expected.iter().collect();
+                let mut actual: Vec<&Value> = actual.iter().collect();
+                expected.sort_by(|a, b| self.order(elem, a, b));
+                actual.sort_by(|a, b| self.order(elem, a, b));
+                expected.into_iter().zip(actual).all(|(e, a)| matches(e, a))
+            }
+        }
+    }
+
+    /// A total order on values of type `ty`, consistent with how strings are
+    /// normalized. Values of the wrong type are ordered by their JSON instead.
+    fn order(&self, ty: &Type, a: &Value, b: &Value) -> Ordering {
+        match (ty, a, b) {
+            (Type::Bool, Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            // ints past 2^53 do not fit in a float, so they are compared exactly
+            (Type::Int, Value::Number(a), Value::Number(b)) if is_int(a) && is_int(b) => {
+                let int = |n: &serde_json::Number| {
+                    n.as_i64().map(i128::from).or(n.as_u64().map(i128::from))
+                };
+                int(a).cmp(&int(b))
+            }
+            (Type::Int | Type::Float, Value::Number(a), Value::Number(b)) => {
+                let float = |n: &serde_json::Number| n.as_f64().unwrap_or(f64::NAN);
+                float(a).total_cmp(&float(b))
+            }
+            (Type::String, Value::String(a), Value::String(b)) => {
+                self.normalize(a).cmp(&self.normalize(b))
+            }
+            (Type::Array(elem, _), Value::Array(a), Value::Array(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| self.order(elem, a, b))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (Type::Map(value), Value::Object(a), Value::Object(b)) => a
+                .iter()
+                .zip(b)
+                .map(|((ka, a), (kb, b))| ka.cmp(kb).then_with(|| self.order(value, a, b)))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            _ => kind(a)
+                .cmp(&kind(b))
+                .then_with(|| a.to_string().cmp(&b.to_string())),
+        }
+    }
+
+    fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let mut s = Cow::Borrowed(s);
+        if self.ignore_whitespace {
+            s = Cow::Owned(s.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        if self.ignore_case {
+            s = Cow::Owned(s.to_lowercase());
+        }
+        s
+    }
+}
+
+fn is_int(n: &serde_json::Number) -> bool {
+    n.is_i64() || n.is_u64()
+}
+
+fn kind(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{ArrayOrder, Comparison, Type};
+
+    fn ty(s: &str) -> Type {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_exact() {
+        let exact = Comparison::default();
+        assert!(exact.matches(&ty("int[]"), &json!([1, 2]), &json!([1, 2])));
+        assert!(!exact.matches(&ty("int[]"), &json!([1, 2]), &json!([2, 1])));
+        assert!(!exact.matches(&ty("float"), &json!(0.3), &json!(0.1 + 0.2)));
+        assert!(!exact.matches(&ty("string"), &json!("a"), &json!("A")));
+        assert!(!exact.matches(&ty("int"), &json!(1), &json!("1")));
+    }
+
+    #[test]
+    fn test_epsilon() {
+        let absolute = Comparison {
+            absolute_epsilon: 1e-6,
+            ..Default::default()
+        };
+        assert!(absolute.matches(&ty("float"), &json!(0.3), &json!(0.1 + 0.2)));
+        assert!(absolute.matches(
+            &ty("map<float[]>"),
+            &json!({ "a": [1.0] }),
+            &json!({ "a": [1.0000001] })
+        ));
+        assert!(!absolute.matches(&ty("float"), &json!(1e9), &json!(1e9 + 1.0)));
+        // ints are still compared exactly
+        assert!(!absolute.matches(&ty("int"), &json!(1), &json!(1.0000001)));
+
+        let relative = Comparison {
+            relative_epsilon: 1e-6,
+            ..Default::default()
+        };
+        assert!(relative.matches(&ty("float"), &json!(1e9), &json!(1e9 + 1.0)));
+        assert!(!relative.matches(&ty("float"), &json!(1.0), &json!(1.1)));
+
+        assert!(Comparison {
+            absolute_epsilon: -1.0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_strings() {
+        let loose = Comparison {
+            ignore_case: true,
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+        assert!(loose.matches(
+            &ty("string"),
+            &json!("Hello  World"),
+            &json!(" hello world\n")
+        ));
+        assert!(!loose.matches(&ty("string"), &json!("Hello World"), &json!("HelloWorld")));
+    }
+
+    #[test]
+    fn test_arrays() {
+        let with = |arrays| Comparison {
+            arrays,
+            ..Default::default()
+        };
+        let ints = ty("int[]");
+
+        let unordered = with(ArrayOrder::Unordered);
+        assert!(unordered.matches(&ints, &json!([1, 2, 2]), &json!([2, 1, 2])));
+        assert!(!unordered.matches(&ints, &json!([1, 2, 2]), &json!([1, 1, 2])));
+
+        let set = with(ArrayOrder::Set);
+        assert!(set.matches(&ints, &json!([1, 2, 2]), &json!([2, 1])));
+        assert!(!set.matches(&ints, &json!([1, 2]), &json!([1, 3])));
+
+        let sorted = with(ArrayOrder::Sorted);
+        assert!(sorted.matches(&ints, &json!([3, 1, 2]), &json!([2, 3, 1])));
+        assert!(!sorted.matches(&ints, &json!([1, 2]), &json!([1, 2, 2])));
+        assert!(sorted.matches(&ty("int[][]"), &json!([[2, 1], [0]]), &json!([[0], [1, 2]])));
+
+        // these only differ past the precision of a float
+        let big = ty("int[]");
+        assert!(sorted.matches(
+            &big,
+            &json!([9007199254740993u64, 9007199254740992u64]),
+            &json!([9007199254740992u64, 9007199254740993u64])
+        ));
+        assert!(!sorted.matches(
+            &big,
+            &json!([9007199254740993u64, 1]),
+            &json!([9007199254740992u64, 1])
+        ));
+
+        let sorted_strings = Comparison {
+            ignore_case: true,
+            ..with(ArrayOrder::Sorted)
+        };
+        assert!(sorted_strings.matches(&ty("string[]"), &json!(["b", "A"]), &json!(["a", "B"])));
+    }
+}
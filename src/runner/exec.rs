@@ -1,18 +1,23 @@
-use std::{borrow::Cow, collections::HashMap, io::BufRead};
+use std::{borrow::Cow, collections::HashMap, io::BufRead, sync::Arc};
 
-use docker_api::{conn::TtyChunk, Docker};
-use eyre::Context;
-use futures::TryStreamExt;
+use eyre::ensure;
 use serde::{Deserialize, Serialize};
 
-use crate::{app::Templates, routes::exec::ExecRequest};
+use crate::{
+    app::Templates,
+    config::Limits,
+    routes::exec::{ExecRequest, RunRequest, Type},
+};
 
-use super::Runner;
+use super::{backend::Job, Phase, Runner};
 
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum GeneratorResult {
     Success(GeneratorSuccess),
+    Invalid(InvalidCase),
+    CompileError(CompileError),
+    LimitExceeded(LimitExceeded),
     Err(ExecutionError),
 }
 
@@ -22,6 +27,74 @@ pub struct GeneratorCase {
     pub output: serde_json::Value,
 }
 
+/// Why a test case does not match the declared function signature
+#[derive(Debug, Serialize, thiserror::Error, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaseError {
+    #[error("Case {case}: expected {expected} arguments, got {actual}")]
+    ArgumentCount {
+        case: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Case {case}: expected argument {argument} to be `{expected}`, got `{actual}`")]
+    Argument {
+        case: usize,
+        argument: usize,
+        expected: Type,
+        actual: serde_json::Value,
+    },
+    #[error("Case {case}: expected output to be `{expected}`, got `{actual}`")]
+    Output {
+        case: usize,
+        expected: Type,
+        actual: serde_json::Value,
+    },
+}
+
+impl GeneratorCase {
+    /// Check that this case matches the signature `inputs -> output`
+    pub fn check(&self, case: usize, inputs: &[Type], output: &Type) -> Result<(), CaseError> {
+        if self.input.len() != inputs.len() {
+            return Err(CaseError::ArgumentCount {
+                case,
+                expected: inputs.len(),
+                actual: self.input.len(),
+            });
+        }
+        if let Some((argument, (ty, value))) = inputs
+            .iter()
+            .zip(&self.input)
+            .enumerate()
+            .find(|(_, (ty, value))| !ty.check(value))
+        {
+            return Err(CaseError::Argument {
+                case,
+                argument,
+                expected: ty.clone(),
+                actual: value.clone(),
+            });
+        }
+        if !output.check(&self.output) {
+            return Err(CaseError::Output {
+                case,
+                expected: output.clone(),
+                actual: self.output.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A generator ran successfully, but produced a case that does not typecheck
+#[derive(Debug, Serialize)]
+pub struct InvalidCase {
+    pub error: CaseError,
+    pub message: String,
+    pub stdout: String,
+    pub stderr: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeneratorSuccess {
     pub cases: Vec<GeneratorCase>,
@@ -32,125 +105,492 @@ pub struct GeneratorSuccess {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExecutionError {
     pub exit_code: isize,
+    /// why the program failed, if it exited successfully without valid results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// The build command of a compiled language failed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompileError {
+    pub exit_code: isize,
+    /// everything the compiler printed
+    pub diagnostics: String,
+}
+
+/// The exercise's checker failed, so the solution could not be graded
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckerError {
+    pub message: String,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Which of its [`Limits`] a container was killed for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum Limit {
+    #[serde(rename = "TimeLimitExceeded")]
+    Time,
+    #[serde(rename = "MemoryLimitExceeded")]
+    Memory,
+    #[serde(rename = "OutputLimitExceeded")]
+    Output,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum RunnerResult {
+    Success(RunnerSuccess),
+    CompileError(CompileError),
+    LimitExceeded(LimitExceeded),
+    Err(ExecutionError),
+    CheckerError(CheckerError),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CaseResult {
+    pub input: Vec<serde_json::Value>,
+    pub expected: serde_json::Value,
+    pub actual: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub passed: bool,
+    /// partial credit between 0 and 1, if the exercise's checker gave any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// why the exercise's checker passed or failed the case
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunnerSuccess {
+    pub cases: Vec<CaseResult>,
+    pub passed: usize,
     pub stdout: String,
     pub stderr: String,
 }
 
+/// Output from a running program, forwarded as it arrives
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputChunk {
+    Stdout(String),
+    Stderr(String),
+}
+
+pub type OutputSender = futures::channel::mpsc::UnboundedSender<OutputChunk>;
+
+/// What the runner template reports for every case it was given
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RunnerCaseOutput {
+    Output(serde_json::Value),
+    Error(String),
+}
+
+#[derive(Serialize)]
+struct RunnerTemplateData<'a> {
+    function_name: &'a str,
+    inputs: &'a [Type],
+    output: &'a Type,
+    /// the arguments of every case, to be rendered with `{{literal value type}}`
+    cases: Vec<Vec<TypedValue<'a>>>,
+}
+
+#[derive(Serialize)]
+struct TypedValue<'a> {
+    value: &'a serde_json::Value,
+    r#type: &'a Type,
+}
+
+/// Where the templates write their results in the work dir, so that stdout
+/// only has what the user's code printed
+pub(crate) const RESULTS_FILE: &str = "results.json";
+
+/// Paths and contents of files to copy into a container
+pub type Files = Vec<(String, Arc<[u8]>)>;
+
+pub struct Output {
+    pub exit_code: isize,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// what the phase produced, if it got far enough: the artifact when
+    /// building, or the contents of [`RESULTS_FILE`] when running
+    pub collected: Option<Vec<u8>>,
+    /// set if the program was killed before it could finish
+    pub limit: Option<Limit>,
+}
+
+impl Output {
+    fn into_error(self) -> ExecutionError {
+        ExecutionError {
+            exit_code: self.exit_code,
+            message: None,
+            stdout: String::from_utf8_lossy_owned(self.stdout),
+            stderr: String::from_utf8_lossy_owned(self.stderr),
+        }
+    }
+
+    /// The program wrote results that can't be read, which the user's code
+    /// could have caused in many ways, like printing `NaN` or overwriting them
+    fn into_invalid_results(self, message: String) -> ExecutionError {
+        ExecutionError {
+            message: Some(message),
+            ..self.into_error()
+        }
+    }
+
+    fn into_compile_error(self) -> CompileError {
+        let mut diagnostics = self.stdout;
+        diagnostics.extend(self.stderr);
+        CompileError {
+            exit_code: self.exit_code,
+            diagnostics: String::from_utf8_lossy_owned(diagnostics),
+        }
+    }
+
+    pub(super) fn into_checker_error(self, message: String) -> CheckerError {
+        CheckerError {
+            message,
+            stdout: String::from_utf8_lossy_owned(self.stdout),
+            stderr: String::from_utf8_lossy_owned(self.stderr),
+        }
+    }
+
+    fn into_limit_exceeded(self, limit: Limit) -> LimitExceeded {
+        LimitExceeded {
+            limit,
+            stdout: String::from_utf8_lossy_owned(self.stdout),
+            stderr: String::from_utf8_lossy_owned(self.stderr),
+        }
+    }
+}
+
 impl Runner {
+    /// Run `files` in a fresh sandbox and collect its output, killing the
+    /// program if it exceeds `limits`. Output is also sent to `forward` as it
+    /// arrives.
+    pub(super) async fn execute(
+        &self,
+        limits: &Limits,
+        phase: Phase,
+        files: &Files,
+        forward: Option<&OutputSender>,
+    ) -> eyre::Result<Output> {
+        let collect = match (phase, &self.lang.build) {
+            (Phase::Build, Some(build)) => self.lang.work_path(&build.artifact),
+            _ => self.lang.work_path(RESULTS_FILE),
+        };
+        let job = Job {
+            limits,
+            phase,
+            files,
+            collect: &collect,
+            forward,
+        };
+        self.backend.execute(job).await
+    }
+
+    /// Build `sources` if needed and run them. Gives the output of the build
+    /// instead if it fails.
+    pub(super) async fn build_and_run(
+        &self,
+        limits: &Limits,
+        sources: Files,
+        forward: Option<&OutputSender>,
+    ) -> eyre::Result<Result<Output, Output>> {
+        let files = match self.build(sources, forward).await? {
+            Ok(files) => files,
+            Err(output) => return Ok(Err(output)),
+        };
+        let output = self.execute(limits, Phase::Run, &files, forward).await?;
+        Ok(Ok(output))
+    }
+
+    /// The harness and the user's code, named as the language expects
+    pub(super) fn sources(&self, harness: String, user_file: &str, user_code: &str) -> Files {
+        vec![
+            (
+                self.lang.work_path(&self.lang.sources.harness),
+                harness.into_bytes().into(),
+            ),
+            (self.lang.work_path(user_file), user_code.as_bytes().into()),
+        ]
+    }
+
     pub async fn run_generator(
         &self,
         templates: &Templates,
-        docker: &Docker,
         cfg: &ExecRequest,
+        forward: Option<&OutputSender>,
     ) -> eyre::Result<GeneratorResult> {
-        let container = self.create_container(docker).await?;
-
         let gen = templates.render_generator(&self.lang, cfg)?;
-        container
-            .copy_file_into("/runner/main.py", gen.into_bytes().as_slice())
-            .await?;
-        container
-            .copy_file_into("/runner/gen.py", cfg.content.as_bytes())
-            .await?;
-
-        let (mut out_stream, _) = container.attach().await?.split();
-        container.start().await?;
-
-        let mut stdout = Vec::new();
-        let mut stderr = Vec::new();
-
-        while let Some(chunk) = out_stream.try_next().await? {
-            match chunk {
-                TtyChunk::StdIn(_) => {} // ignore
-                TtyChunk::StdOut(data) => stdout.extend(data),
-                TtyChunk::StdErr(data) => stderr.extend(data),
+        let sources = self.sources(gen, &self.lang.sources.generator, &cfg.content);
+        let limits = self.limits.tighten(&cfg.limits);
+        let output = match self.build_and_run(&limits, sources, forward).await? {
+            Ok(output) => output,
+            Err(build) => {
+                return Ok(match build.limit {
+                    Some(limit) => GeneratorResult::LimitExceeded(build.into_limit_exceeded(limit)),
+                    None => GeneratorResult::CompileError(build.into_compile_error()),
+                })
+            }
+        };
+
+        if let Some(limit) = output.limit {
+            return Ok(GeneratorResult::LimitExceeded(
+                output.into_limit_exceeded(limit),
+            ));
+        }
+        let Some(results) = output
+            .collected
+            .as_deref()
+            .filter(|_| output.exit_code == 0)
+        else {
+            return Ok(GeneratorResult::Err(output.into_error()));
+        };
+
+        // the results are whatever the program wrote, so they are not repeated
+        // in the error
+        let cases: Vec<GeneratorCase> = match serde_json::from_slice(results) {
+            Ok(cases) => cases,
+            Err(e) => {
+                let message = format!("Invalid results: {e}");
+                return Ok(GeneratorResult::Err(output.into_invalid_results(message)));
             }
+        };
+
+        let invalid = cases
+            .iter()
+            .enumerate()
+            .find_map(|(i, case)| case.check(i, &cfg.inputs, &cfg.output).err());
+        if let Some(error) = invalid {
+            return Ok(GeneratorResult::Invalid(InvalidCase {
+                message: error.to_string(),
+                error,
+                stdout: String::from_utf8_lossy_owned(output.stdout),
+                stderr: String::from_utf8_lossy_owned(output.stderr),
+            }));
         }
 
-        container.wait().await?;
-        let exit_code = container
-            .inspect()
-            .await?
-            .state
-            .and_then(|s| s.exit_code)
-            .unwrap_or(-1);
-
-        if exit_code == 0 {
-            let (_, last_line) = stdout
-                .trim_ascii_end()
-                .rsplit_once(|b| *b == b'\n')
-                .unwrap_or((b"", stdout.trim_ascii_end()));
-
-            let cases: Vec<GeneratorCase> =
-                serde_json::from_slice(last_line).with_context(|| {
-                    format!(
-                        "While parsing generator output:\n{}\n{}",
-                        String::from_utf8_lossy(&stdout),
-                        String::from_utf8_lossy(&stderr)
-                    )
-                })?;
-
-            Ok(GeneratorResult::Success(GeneratorSuccess {
-                cases,
-                stdout: String::from_utf8_lossy_owned(stdout),
-                stderr: String::from_utf8_lossy_owned(stderr),
-            }))
-        } else {
-            Ok(GeneratorResult::Err(ExecutionError {
-                exit_code,
-                stdout: String::from_utf8_lossy_owned(stdout),
-                stderr: String::from_utf8_lossy_owned(stderr),
-            }))
+        Ok(GeneratorResult::Success(GeneratorSuccess {
+            cases,
+            stdout: String::from_utf8_lossy_owned(output.stdout),
+            stderr: String::from_utf8_lossy_owned(output.stderr),
+        }))
+    }
+
+    /// Run a solution against a set of test cases and grade its output
+    pub async fn run_solution(
+        &self,
+        templates: &Templates,
+        req: &RunRequest,
+        forward: Option<&OutputSender>,
+    ) -> eyre::Result<RunnerResult> {
+        // callers check the cases against the signature, so none are cut short
+        ensure!(
+            req.cases.iter().all(|c| c.input.len() == req.inputs.len()),
+            "Cases do not have {} arguments",
+            req.inputs.len()
+        );
+        let cases = req
+            .cases
+            .iter()
+            .map(|c| {
+                c.input
+                    .iter()
+                    .zip(&req.inputs)
+                    .map(|(value, r#type)| TypedValue { value, r#type })
+                    .collect()
+            })
+            .collect();
+        let data = RunnerTemplateData {
+            function_name: &req.function_name,
+            inputs: &req.inputs,
+            output: &req.output,
+            cases,
+        };
+        let runner = templates.render_runner(&self.lang, &data)?;
+        let sources = self.sources(runner, &self.lang.sources.solution, &req.content);
+        let limits = self.limits.tighten(&req.limits);
+        let output = match self.build_and_run(&limits, sources, forward).await? {
+            Ok(output) => output,
+            Err(build) => {
+                return Ok(match build.limit {
+                    Some(limit) => RunnerResult::LimitExceeded(build.into_limit_exceeded(limit)),
+                    None => RunnerResult::CompileError(build.into_compile_error()),
+                })
+            }
+        };
+
+        if let Some(limit) = output.limit {
+            return Ok(RunnerResult::LimitExceeded(
+                output.into_limit_exceeded(limit),
+            ));
         }
+        let Some(results) = output
+            .collected
+            .as_deref()
+            .filter(|_| output.exit_code == 0)
+        else {
+            return Ok(RunnerResult::Err(output.into_error()));
+        };
+
+        let outputs = match serde_json::from_slice::<Vec<RunnerCaseOutput>>(results) {
+            Ok(outputs) if outputs.len() == req.cases.len() => outputs,
+            Ok(outputs) => {
+                let message = format!(
+                    "Expected {} results, got {}",
+                    req.cases.len(),
+                    outputs.len()
+                );
+                return Ok(RunnerResult::Err(output.into_invalid_results(message)));
+            }
+            Err(e) => {
+                let message = format!("Invalid results: {e}");
+                return Ok(RunnerResult::Err(output.into_invalid_results(message)));
+            }
+        };
+
+        let cases: Vec<CaseResult> = req
+            .cases
+            .iter()
+            .zip(outputs)
+            .map(|(case, out)| {
+                let (actual, error) = match out {
+                    RunnerCaseOutput::Output(v) => (Some(v), None),
+                    RunnerCaseOutput::Error(e) => (None, Some(e)),
+                };
+                CaseResult {
+                    input: case.input.clone(),
+                    expected: case.output.clone(),
+                    passed: actual
+                        .as_ref()
+                        .is_some_and(|a| req.comparison.matches(&req.output, &case.output, a)),
+                    actual,
+                    error,
+                    score: None,
+                    message: None,
+                }
+            })
+            .collect();
+
+        Ok(RunnerResult::Success(RunnerSuccess {
+            passed: cases.iter().filter(|c| c.passed).count(),
+            cases,
+            stdout: String::from_utf8_lossy_owned(output.stdout),
+            stderr: String::from_utf8_lossy_owned(output.stderr),
+        }))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::fs;
+    use std::{fs, time::Duration};
 
     use docker_api::Docker;
     use serde_json::json;
 
     use crate::{
         app::Templates,
-        config,
+        config::{self, LimitOverrides},
         langs::Languages,
         routes::exec::Type,
         runner::{
             self,
-            exec::{ExecRequest, GeneratorCase},
+            backend::fake::{self, ReplayBackend},
+            build::CompileCache,
+            exec::{CaseError, ExecRequest, GeneratorCase, Limit, LimitExceeded, RunRequest},
+            Phase,
         },
     };
 
-    #[tokio::test]
-    async fn test_generator() {
+    /// Runners for programs on the host, which has to have python installed
+    fn host_setup() -> (Templates, runner::RunnerRegistry) {
+        fake::registry(fake::host)
+    }
+
+    /// Runners in the images of `config.yaml`, for the tests of languages that
+    /// are not installed on the host
+    async fn docker_setup() -> (Templates, runner::RunnerRegistry) {
         let cfg: config::Config =
             serde_yaml::from_str(&fs::read_to_string("config.yaml").unwrap()).unwrap();
         let docker = Docker::new(&cfg.docker.host).unwrap();
         let langs = Languages::new().unwrap();
         let mut templates = Templates::new(handlebars::Handlebars::new());
-        let reg = runner::generate_registry(&cfg.docker, &docker, &langs, &mut templates)
+        let backends = runner::backend::docker::backends(&cfg.docker, &docker, &langs)
             .await
             .unwrap();
+        let backends = backends.into_iter().map(|b| b as _).collect();
+        let reg = runner::generate_registry(&cfg.docker, &langs, backends, &mut templates).unwrap();
+        (templates, reg)
+    }
 
-        let gen = "def gen(ctx):       \n\
-                           \tctx.input(1, 1) \n\
-                           \tctx.output(2)   \n";
+    #[test]
+    fn test_check_case() {
+        let case: GeneratorCase = serde_json::from_value(json!({
+            "input": [[1, 2], "a"],
+            "output": 2
+        }))
+        .unwrap();
+        let inputs = ["int[]".parse().unwrap(), Type::String];
+
+        assert_eq!(case.check(0, &inputs, &Type::Int), Ok(()));
+        assert_eq!(
+            case.check(3, &inputs[..1], &Type::Int),
+            Err(CaseError::ArgumentCount {
+                case: 3,
+                expected: 1,
+                actual: 2
+            })
+        );
+        assert_eq!(
+            case.check(0, &[Type::Int, Type::String], &Type::Int),
+            Err(CaseError::Argument {
+                case: 0,
+                argument: 0,
+                expected: Type::Int,
+                actual: json!([1, 2])
+            })
+        );
+        assert_eq!(
+            case.check(0, &inputs, &Type::Bool),
+            Err(CaseError::Output {
+                case: 0,
+                expected: Type::Bool,
+                actual: json!(2)
+            })
+        );
+    }
 
-        let runner::exec::GeneratorResult::Success(output) = reg["python"]
+    /// Generate two `1 + 1` cases in `language`
+    async fn generate_add(
+        (templates, reg): (Templates, runner::RunnerRegistry),
+        language: &str,
+        gen: &str,
+    ) {
+        let runner::exec::GeneratorResult::Success(output) = reg[language]
             .run_generator(
                 &templates,
-                &docker,
                 &ExecRequest {
-                    language: "python".to_string(),
+                    language: language.to_string(),
                     content: gen.to_string(),
                     inputs: vec![Type::Int, Type::Int],
                     output: Type::Int,
                     hidden_cases: 0,
                     visible_cases: 0,
                     generate_cases: 2,
+                    limits: Default::default(),
                 },
+                None,
             )
             .await
             .unwrap()
@@ -171,4 +611,454 @@ mod test {
         .unwrap();
         assert_eq!(output.cases, expected)
     }
+
+    #[tokio::test]
+    async fn test_generator() {
+        let gen = "def gen(ctx):       \n\
+                           \tctx.input(1, 1) \n\
+                           \tctx.output(2)   \n";
+        generate_add(host_setup(), "python", gen).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs docker"]
+    async fn test_generator_javascript() {
+        let gen = "function gen(ctx) {  \n\
+                           \tctx.input(1, 1); \n\
+                           \tctx.output(2);   \n\
+                           }                   \n";
+        generate_add(docker_setup().await, "javascript", gen).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs docker"]
+    async fn test_generator_typescript() {
+        let gen = "interface Ctx {                             \n\
+                           \tinput(...args: number[]): void;         \n\
+                           \toutput(value: number): void;            \n\
+                           }                                           \n\
+                           const gen = (ctx: Ctx): void => {           \n\
+                           \tctx.input(1, 1);                        \n\
+                           \tctx.output(2);                          \n\
+                           };                                          \n";
+        generate_add(docker_setup().await, "typescript", gen).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs docker"]
+    async fn test_generator_rust() {
+        let gen = "fn gen(ctx: &mut Context) { \n\
+                           \tctx.input(1, 1);          \n\
+                           \tctx.output(2);            \n\
+                           }                            \n";
+        generate_add(docker_setup().await, "rust", gen).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs docker"]
+    async fn test_generator_c() {
+        let gen = "void gen(struct context *ctx) { \n\
+                           \tctx_input(ctx, 1, 1);         \n\
+                           \tctx_output(ctx, 2);           \n\
+                           }                                \n";
+        generate_add(docker_setup().await, "c", gen).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs docker"]
+    async fn test_generator_cpp() {
+        let gen = "void gen(Context &ctx) { \n\
+                           \tctx.input(1, 1);       \n\
+                           \tctx.output(2);         \n\
+                           }                         \n";
+        generate_add(docker_setup().await, "cpp", gen).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs docker"]
+    async fn test_generator_java() {
+        let gen = "class Gen {                         \n\
+                           \tstatic void gen(Context ctx) {     \n\
+                           \t\tctx.input(1, 1);                 \n\
+                           \t\tctx.output(2);                   \n\
+                           \t}                                  \n\
+                           }                                    \n";
+        generate_add(docker_setup().await, "java", gen).await;
+    }
+
+    #[tokio::test]
+    async fn test_solution() {
+        let (templates, reg) = host_setup();
+
+        let solution = "def add(a, b):   \n\
+                        \treturn a + b   \n";
+
+        let cases: Vec<GeneratorCase> = serde_json::from_value(json!([
+            {
+                "input": [1, 1],
+                "output": 2
+            },
+            {
+                "input": [2, 2],
+                "output": 5
+            }
+        ]))
+        .unwrap();
+
+        let runner::exec::RunnerResult::Success(output) = reg["python"]
+            .run_solution(
+                &templates,
+                &RunRequest {
+                    language: "python".to_string(),
+                    content: solution.to_string(),
+                    function_name: "add".to_string(),
+                    inputs: vec![Type::Int, Type::Int],
+                    output: Type::Int,
+                    cases,
+                    comparison: Default::default(),
+                    checker: None,
+                    limits: Default::default(),
+                },
+                None,
+            )
+            .await
+            .unwrap()
+        else {
+            panic!()
+        };
+
+        assert_eq!(output.passed, 1);
+        assert!(output.cases[0].passed);
+        assert!(!output.cases[1].passed);
+        assert_eq!(output.cases[1].actual, Some(json!(4)));
+    }
+
+    #[tokio::test]
+    async fn test_solution_error() {
+        let (templates, reg) = host_setup();
+        let solution = "def add(a, b):                     \n\
+                        \tif a == 2:                       \n\
+                        \t\traise ValueError('no twos')    \n\
+                        \treturn a + b                     \n";
+        let mut req = add_run_request("python", solution);
+        req.cases
+            .push(serde_json::from_value(json!({ "input": [2, 2], "output": 4 })).unwrap());
+
+        let runner::exec::RunnerResult::Success(output) = reg["python"]
+            .run_solution(&templates, &req, None)
+            .await
+            .unwrap()
+        else {
+            panic!()
+        };
+        assert!(output.cases[0].passed);
+        assert!(!output.cases[1].passed);
+        assert_eq!(output.cases[1].actual, None);
+        assert!(output.cases[1].error.as_ref().unwrap().contains("no twos"));
+    }
+
+    #[tokio::test]
+    async fn test_source_names() {
+        let (mut templates, reg) = host_setup();
+        let mut lang = reg["python"].lang.clone();
+        lang.sources.solution = "answer.py".to_string();
+        templates.register_lang(&lang).unwrap();
+        let runner = runner::Runner {
+            backend: fake::host(&lang),
+            lang,
+            limits: fake::limits(),
+            compile_cache: CompileCache::new(1),
+        };
+
+        let solution = "def add(a, b):   \n\
+                        \treturn a + b   \n";
+        let runner::exec::RunnerResult::Success(output) = runner
+            .run_solution(&templates, &add_run_request("python", solution), None)
+            .await
+            .unwrap()
+        else {
+            panic!()
+        };
+        assert_eq!(output.passed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_time_limit() {
+        let (templates, reg) = host_setup();
+
+        let gen = "def gen(ctx):   \n\
+                           \twhile True: \n\
+                           \t\tpass     \n";
+
+        let res = reg["python"]
+            .run_generator(
+                &templates,
+                &ExecRequest {
+                    language: "python".to_string(),
+                    content: gen.to_string(),
+                    inputs: vec![Type::Int],
+                    output: Type::Int,
+                    hidden_cases: 0,
+                    visible_cases: 0,
+                    generate_cases: 1,
+                    limits: LimitOverrides {
+                        timeout: Some(Duration::from_secs(1)),
+                        ..Default::default()
+                    },
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            res,
+            runner::exec::GeneratorResult::LimitExceeded(LimitExceeded {
+                limit: Limit::Time,
+                ..
+            })
+        ));
+    }
+
+    fn add_request(language: &str) -> ExecRequest {
+        ExecRequest {
+            language: language.to_string(),
+            content: String::new(),
+            inputs: vec![Type::Int, Type::Int],
+            output: Type::Int,
+            hidden_cases: 0,
+            visible_cases: 0,
+            generate_cases: 1,
+            limits: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_generator() {
+        let replay = ReplayBackend::new([
+            fake::exited(0, Some(br#"[{"input": [1, "a"], "output": 2}]"#)),
+            fake::exited(1, None),
+            fake::killed(Limit::Memory),
+            fake::exited(0, Some(br#"[{"input": [NaN, 1], "output": 2}]"#)),
+        ]);
+        let (templates, reg) = fake::registry(|_| replay.clone());
+        let run = async || {
+            reg["python"]
+                .run_generator(&templates, &add_request("python"), None)
+                .await
+                .unwrap()
+        };
+
+        let runner::exec::GeneratorResult::Invalid(invalid) = run().await else {
+            panic!()
+        };
+        assert_eq!(
+            invalid.error,
+            CaseError::Argument {
+                case: 0,
+                argument: 1,
+                expected: Type::Int,
+                actual: json!("a")
+            }
+        );
+        assert!(matches!(
+            run().await,
+            runner::exec::GeneratorResult::Err(e) if e.exit_code == 1
+        ));
+        assert!(matches!(
+            run().await,
+            runner::exec::GeneratorResult::LimitExceeded(LimitExceeded {
+                limit: Limit::Memory,
+                ..
+            })
+        ));
+        // not a 500, since the generator wrote them
+        assert!(matches!(
+            run().await,
+            runner::exec::GeneratorResult::Err(e)
+                if e.message.as_ref().is_some_and(|m| m.starts_with("Invalid results"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replay_build() {
+        let results = br#"[{"input": [1, 1], "output": 2}]"#;
+        let replay = ReplayBackend::new([
+            fake::exited(1, None),
+            fake::exited(0, Some(b"program")),
+            fake::exited(0, Some(results)),
+            fake::exited(0, Some(results)),
+        ]);
+        let (templates, reg) = fake::registry(|_| replay.clone());
+        let mut req = add_request("rust");
+        let mut run = async |content: &str| {
+            req.content = content.to_string();
+            reg["rust"]
+                .run_generator(&templates, &req, None)
+                .await
+                .unwrap()
+        };
+
+        assert!(matches!(
+            run("fn gen() {").await,
+            runner::exec::GeneratorResult::CompileError(e) if e.exit_code == 1
+        ));
+        assert!(matches!(
+            run("fn gen() {}").await,
+            runner::exec::GeneratorResult::Success(_)
+        ));
+        // the artifact of the same code is cached
+        assert!(matches!(
+            run("fn gen() {}").await,
+            runner::exec::GeneratorResult::Success(_)
+        ));
+
+        let jobs = replay.jobs.lock().unwrap();
+        let phases: Vec<Phase> = jobs.iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(phases, [Phase::Build, Phase::Build, Phase::Run, Phase::Run]);
+        assert_eq!(jobs[2].1[0].0, "/runner/main");
+        assert_eq!(&*jobs[2].1[0].1, b"program");
+    }
+
+    fn add_run_request(language: &str, content: &str) -> RunRequest {
+        RunRequest {
+            language: language.to_string(),
+            content: content.to_string(),
+            function_name: "add".to_string(),
+            inputs: vec![Type::Int, Type::Int],
+            output: Type::Int,
+            cases: vec![serde_json::from_value(json!({ "input": [1, 1], "output": 2 })).unwrap()],
+            comparison: Default::default(),
+            checker: None,
+            limits: Default::default(),
+        }
+    }
+
+    /// Run a solution taking a string and a map in `language` against a replayed
+    /// runner, and return the harness it was given
+    async fn render_runner(language: &str) -> String {
+        let replay = ReplayBackend::new([fake::exited(
+            0,
+            Some(br#"[{"output": "a"}, {"error": "TypeError: boom"}]"#),
+        )]);
+        let (templates, reg) = fake::registry(|_| replay.clone());
+        let req = RunRequest {
+            inputs: vec![Type::String, "map<int[]>".parse().unwrap()],
+            output: Type::String,
+            cases: serde_json::from_value(json!([
+                { "input": ["a\"b\n\u{1}", { "k": [1, 2] }], "output": "a" },
+                { "input": ["", {}], "output": "" },
+            ]))
+            .unwrap(),
+            ..add_run_request(language, "user code")
+        };
+
+        let runner::exec::RunnerResult::Success(output) = reg[language]
+            .run_solution(&templates, &req, None)
+            .await
+            .unwrap()
+        else {
+            panic!()
+        };
+        assert!(output.cases[0].passed);
+        assert_eq!(output.cases[1].error.as_deref(), Some("TypeError: boom"));
+
+        let jobs = replay.jobs.lock().unwrap();
+        let [(Phase::Run, files)] = &jobs[..] else {
+            panic!()
+        };
+        let extension = &reg[language].lang.extension;
+        assert_eq!(files[1].0, format!("/runner/solution.{extension}"));
+        assert_eq!(&*files[1].1, b"user code");
+        assert_eq!(files[0].0, format!("/runner/main.{extension}"));
+        String::from_utf8(files[0].1.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_render_javascript() {
+        let harness = render_runner("javascript").await;
+        assert!(harness.contains(r#"    ["a\"b\n\u{1}", {"k": [1, 2]}, ],"#));
+        assert!(harness.contains(r#"    ["", {}, ],"#));
+        assert!(harness.contains(r#"fs.readFileSync("solution.js", "utf8") + "\n;add""#));
+    }
+
+    #[tokio::test]
+    async fn test_render_typescript() {
+        let harness = render_runner("typescript").await;
+        assert!(harness.contains(r#"    ["a\"b\n\u{1}", {"k": [1, 2]}, ],"#));
+        // solutions are run as plain JavaScript, once node has stripped their types
+        assert!(harness.contains(r#"fs.readFileSync("solution.ts", "utf8")"#));
+        assert!(
+            harness.contains(r#"stripTypeScriptTypes(source, { mode: "transform" }) + "\n;add""#)
+        );
+    }
+
+    /// Build and run a solution in the compiled `language` against a replayed
+    /// backend, and return the harness it was built with, which has the case's
+    /// `input` compiled into it
+    async fn render_compiled(
+        language: &str,
+        inputs: [&str; 2],
+        input: serde_json::Value,
+    ) -> String {
+        let replay = ReplayBackend::new([
+            fake::exited(0, Some(b"program")),
+            fake::exited(0, Some(br#"[{"output": 0}]"#)),
+        ]);
+        let (templates, reg) = fake::registry(|_| replay.clone());
+        let req = RunRequest {
+            inputs: inputs.iter().map(|ty| ty.parse().unwrap()).collect(),
+            cases: vec![serde_json::from_value(json!({ "input": input, "output": 0 })).unwrap()],
+            ..add_run_request(language, "user code")
+        };
+
+        let runner::exec::RunnerResult::Success(output) = reg[language]
+            .run_solution(&templates, &req, None)
+            .await
+            .unwrap()
+        else {
+            panic!()
+        };
+        assert!(output.cases[0].passed);
+
+        let jobs = replay.jobs.lock().unwrap();
+        let [(Phase::Build, files), (Phase::Run, _)] = &jobs[..] else {
+            panic!()
+        };
+        String::from_utf8(files[0].1.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_render_compiled() {
+        let input = json!(["a\u{1}1", [[1, 2]]]);
+        let cases = [
+            (
+                "c",
+                ["string", "int"],
+                json!(["a\u{1}1", -3]),
+                r#"add("a\0011", -3LL)"#,
+            ),
+            (
+                "cpp",
+                ["string", "int[2][]"],
+                input.clone(),
+                r#"add(std::string("a\0011", 3), std::vector<std::array<long long, 2>>{std::array<long long, 2>{1LL, 2LL}})"#,
+            ),
+            (
+                "rust",
+                ["string", "int[2][]"],
+                input.clone(),
+                r#"super::add("a\u{1}1".to_string(), vec![[1, 2]], )"#,
+            ),
+            (
+                "java",
+                ["string", "int[][]"],
+                input,
+                r#"Solution.add("a\u00011", new long[][]{new long[]{1L, 2L}})"#,
+            ),
+        ];
+        for (language, inputs, input, call) in cases {
+            let harness = render_compiled(language, inputs, input).await;
+            assert!(harness.contains(call), "{language}:\n{harness}");
+        }
+    }
 }
@@ -0,0 +1,135 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use docker_api::Container;
+use tokio::sync::Notify;
+
+use super::{backend::docker::DockerBackend, cleanup::remove_container, Phase};
+
+/// Containers that have been created ahead of time, so that running code only
+/// has to copy files in and start one
+pub struct ContainerPool {
+    idle: Mutex<VecDeque<Container>>,
+    /// IDs of the containers executions are using, pooled or not, which are
+    /// old when they were pooled for a while but must not be reaped
+    pub(super) in_use: InUse,
+    max_idle: usize,
+    taken: Notify,
+}
+
+pub type InUse = Arc<Mutex<HashSet<String>>>;
+
+impl ContainerPool {
+    pub fn new(max_idle: usize) -> Self {
+        Self {
+            idle: Mutex::new(VecDeque::with_capacity(max_idle)),
+            in_use: Default::default(),
+            max_idle,
+            taken: Notify::new(),
+        }
+    }
+
+    /// Take an idle container, if there is one, and have it replaced in the
+    /// background. It counts as in use until its [`ContainerGuard`] removes it.
+    ///
+    /// [`ContainerGuard`]: super::cleanup::ContainerGuard
+    pub fn take(&self) -> Option<Container> {
+        // moved while the idle containers are locked, so that it is never in neither
+        let mut idle = self.idle.lock().unwrap();
+        let container = idle.pop_front();
+        if let Some(container) = &container {
+            let id = container.id().to_string();
+            self.in_use.lock().unwrap().insert(id);
+        }
+        drop(idle);
+        self.taken.notify_one();
+        container
+    }
+
+    /// Whether the container is idle in this pool, or in use by an execution
+    pub fn contains(&self, id: &str) -> bool {
+        let idle = self.idle.lock().unwrap();
+        idle.iter().any(|c| c.id().as_ref() == id) || self.in_use.lock().unwrap().contains(id)
+    }
+
+    fn len(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    fn push(&self, container: Container) {
+        self.idle.lock().unwrap().push_back(container);
+    }
+
+    fn snapshot(&self) -> Vec<Container> {
+        self.idle.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Remove a container if it is still idle, rather than taken in the meantime
+    fn remove_idle(&self, id: &str) -> bool {
+        let mut idle = self.idle.lock().unwrap();
+        let len = idle.len();
+        idle.retain(|c| c.id().as_ref() != id);
+        idle.len() < len
+    }
+}
+
+impl DockerBackend {
+    /// Keep the pool filled, replacing containers as they are taken and
+    /// checking on the idle ones every `health_check_interval`
+    pub async fn maintain_pool(self: Arc<Self>) {
+        if self.pool.max_idle == 0 {
+            return;
+        }
+
+        loop {
+            while self.pool.len() < self.pool.max_idle {
+                match self.create_container(&self.limits, Phase::Run).await {
+                    Ok(container) => self.pool.push(container),
+                    Err(e) => {
+                        // try again at the next health check
+                        tracing::error!(
+                            "Could not create pooled `{}` container: {e}",
+                            self.lang.name
+                        );
+                        break;
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = self.pool.taken.notified() => {}
+                _ = tokio::time::sleep(self.health_check_interval) => self.check_pool().await,
+            }
+        }
+    }
+
+    /// Remove idle containers that are gone or no longer waiting to be started.
+    /// Containers stay in the pool while they are checked, since they may be
+    /// taken and started in the meantime.
+    async fn check_pool(&self) {
+        for container in self.pool.snapshot() {
+            let status = match container.inspect().await {
+                Ok(info) => info.state.and_then(|s| s.status),
+                Err(e) => {
+                    if self.pool.remove_idle(container.id().as_ref()) {
+                        tracing::warn!("Dropping pooled container {}: {e}", container.id());
+                    }
+                    continue;
+                }
+            };
+
+            if status.as_deref() == Some("created")
+                || !self.pool.remove_idle(container.id().as_ref())
+            {
+                continue;
+            }
+            tracing::warn!(
+                "Dropping pooled container {} with status {status:?}",
+                container.id()
+            );
+            remove_container(container).await;
+        }
+    }
+}
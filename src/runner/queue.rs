@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+
+use crate::config::QueueConfig;
+
+/// Header telling callers how many jobs were ahead of theirs
+pub const QUEUE_POSITION: &str = "x-queue-position";
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum QueueError {
+    #[error("You already have {0} programs running, wait for them to finish")]
+    UserLimit(usize),
+    #[error("Too many programs are waiting to run, try again later")]
+    Full,
+}
+
+/// Limits how many containers run at once, queueing the rest in order
+pub struct ExecQueue {
+    running: Arc<Semaphore>,
+    max_queued: usize,
+    max_per_user: usize,
+    state: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// jobs waiting for a permit
+    queued: usize,
+    /// jobs waiting or running, for every user that has any
+    per_user: HashMap<Uuid, usize>,
+}
+
+impl ExecQueue {
+    pub fn new(cfg: &QueueConfig) -> Arc<Self> {
+        Arc::new(Self {
+            running: Arc::new(Semaphore::new(cfg.max_running)),
+            max_queued: cfg.max_queued,
+            max_per_user: cfg.max_per_user,
+            state: Mutex::default(),
+        })
+    }
+
+    /// Take a place in the queue for one of `user_id`'s jobs
+    pub fn join(self: &Arc<Self>, user_id: Uuid) -> Result<Ticket, QueueError> {
+        let mut state = self.state.lock().unwrap();
+
+        let jobs = state.per_user.get(&user_id).copied().unwrap_or(0);
+        if jobs >= self.max_per_user {
+            return Err(QueueError::UserLimit(jobs));
+        }
+        // the semaphore is fair, so everyone queued before us goes first
+        let position = (state.queued + 1).saturating_sub(self.running.available_permits());
+        if position > self.max_queued {
+            return Err(QueueError::Full);
+        }
+
+        state.queued += 1;
+        *state.per_user.entry(user_id).or_default() += 1;
+
+        Ok(Ticket {
+            queue: self.clone(),
+            user_id,
+            position,
+            permit: None,
+        })
+    }
+}
+
+/// A job's place in an [`ExecQueue`], which it gives up when dropped
+pub struct Ticket {
+    queue: Arc<ExecQueue>,
+    user_id: Uuid,
+    /// how many jobs were waiting ahead of this one when it joined
+    pub position: usize,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Ticket {
+    /// Wait until this job may run
+    pub async fn ready(&mut self) {
+        if self.permit.is_some() {
+            return;
+        }
+        let permit = self
+            .queue
+            .running
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        self.queue.state.lock().unwrap().queued -= 1;
+        self.permit = Some(permit);
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        if self.permit.is_none() {
+            state.queued -= 1;
+        }
+        if let Some(jobs) = state.per_user.get_mut(&self.user_id) {
+            *jobs -= 1;
+            if *jobs == 0 {
+                state.per_user.remove(&self.user_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue() {
+        let queue = ExecQueue::new(&QueueConfig {
+            max_running: 1,
+            max_queued: 1,
+            max_per_user: 2,
+            retry_after: Default::default(),
+        });
+        let (a, b) = (Uuid::now_v7(), Uuid::now_v7());
+
+        let mut first = queue.join(a).unwrap();
+        first.ready().await;
+        assert_eq!(first.position, 0);
+
+        let second = queue.join(a).unwrap();
+        assert_eq!(second.position, 1);
+        assert_eq!(queue.join(a).err(), Some(QueueError::UserLimit(2)));
+        assert_eq!(queue.join(b).err(), Some(QueueError::Full));
+
+        drop(second);
+        let mut third = queue.join(b).unwrap();
+        drop(first);
+        third.ready().await;
+        assert_eq!(queue.join(a).unwrap().position, 1);
+    }
+}
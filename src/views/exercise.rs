@@ -0,0 +1,84 @@
+use chrono::NaiveDateTime;
+use entity::{exercise, test_case};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct ExerciseView {
+    pub exercise_id: Uuid,
+    pub owned: bool,
+    pub title: String,
+    pub description: String,
+    pub function_name: String,
+    pub args: serde_json::Value,
+    pub output: serde_json::Value,
+    pub comparison: serde_json::Value,
+    pub solution_lang: String,
+    pub starting_code: Option<String>,
+    pub created: NaiveDateTime,
+    pub updated: NaiveDateTime,
+
+    // only visible to the owner of the exercise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solution: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generator_lang: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checker_lang: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_table: Option<serde_json::Value>,
+}
+
+impl ExerciseView {
+    /// View an exercise as `user_id`, hiding the solution from anyone but its owner
+    pub fn new(exercise: &exercise::Model, user_id: Uuid) -> Self {
+        let owned = exercise.user_id == user_id;
+
+        Self {
+            exercise_id: exercise.exercise_id,
+            owned,
+            title: exercise.title.clone(),
+            description: exercise.description.clone(),
+            function_name: exercise.function_name.clone(),
+            args: exercise.args.clone(),
+            output: exercise.output.clone(),
+            comparison: exercise.comparison.clone(),
+            solution_lang: exercise.solution_lang.clone(),
+            starting_code: exercise.starting_code.clone(),
+            created: exercise.created,
+            updated: exercise.updated,
+
+            solution: owned.then(|| exercise.solution.clone()),
+            generator_lang: exercise.generator_lang.clone().filter(|_| owned),
+            generator: exercise.generator.clone().filter(|_| owned),
+            checker_lang: exercise.checker_lang.clone().filter(|_| owned),
+            checker: exercise.checker.clone().filter(|_| owned),
+            generated_table: owned.then(|| exercise.generated_table.clone()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TestCaseView {
+    pub test_case_id: Uuid,
+    pub position: i32,
+    pub input: serde_json::Value,
+    pub output: serde_json::Value,
+    pub hidden: bool,
+}
+
+impl TestCaseView {
+    pub fn new(case: &test_case::Model) -> Self {
+        Self {
+            test_case_id: case.test_case_id,
+            position: case.position,
+            input: case.input.clone(),
+            output: case.output.clone(),
+            hidden: case.hidden,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+
+use crate::{
+    langs::{ComplexType, LangInfo, LangType},
+    routes::exec::Type,
+};
+
+#[derive(Serialize)]
+pub struct LangView {
+    pub name: String,
+    pub r#type: LangType,
+    pub types: TypeFeatures,
+    pub extension: String,
+    pub editor_mode: Option<String>,
+    /// whether exercises can be graded by checkers in this language
+    pub checkers: bool,
+    /// markdown
+    pub docs: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Which types a language can represent, beyond `bool`, `int`, `float` and `string`
+#[derive(Serialize)]
+pub struct TypeFeatures {
+    pub arrays: bool,
+    pub variable_length_arrays: bool,
+    pub complex_types: Vec<ComplexType>,
+}
+
+impl From<&LangInfo> for LangView {
+    fn from(lang: &LangInfo) -> Self {
+        let supports = |ty: Type| lang.unsupported(&ty).is_none();
+        let int = || Box::new(Type::Int);
+
+        Self {
+            name: lang.name.clone(),
+            r#type: lang.r#type,
+            types: TypeFeatures {
+                arrays: supports(Type::Array(int(), Some(1))),
+                variable_length_arrays: supports(Type::Array(int(), None)),
+                complex_types: lang
+                    .complex_types
+                    .iter()
+                    .copied()
+                    .filter(|complex| match complex {
+                        ComplexType::Map => supports(Type::Map(int())),
+                    })
+                    .collect(),
+            },
+            extension: lang.extension.clone(),
+            editor_mode: lang.editor_mode.clone(),
+            checkers: lang.checkers,
+            docs: lang.docs.clone(),
+            version: lang.version.clone(),
+        }
+    }
+}
@@ -1,8 +1,14 @@
-use axum::{http::StatusCode, response::IntoResponse};
-use std::fmt::Display;
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use std::{fmt::Display, time::Duration};
 
 pub mod auth;
+pub mod exercise;
+pub mod lang;
 pub mod session;
+pub mod submission;
 
 macro response($status:ident, $res:ident) {
     /// Return a response with the status code
@@ -22,17 +28,35 @@ response!(BAD_REQUEST, bad_request);
 response!(UNAUTHORIZED, unauthorized);
 response!(FORBIDDEN, forbidden);
 response!(NOT_FOUND, not_found);
+response!(TOO_MANY_REQUESTS, too_many_requests);
 response!(INTERNAL_SERVER_ERROR, internal);
+response!(SERVICE_UNAVAILABLE, service_unavailable);
 
 #[derive(Default)]
 pub struct Error {
     pub status: StatusCode,
     pub report: Option<eyre::Error>,
+    /// sent as `Retry-After`, for `429` and `503` responses
+    pub retry_after: Option<Duration>,
+}
+
+impl Error {
+    pub fn retry_after(self, retry_after: Duration) -> Self {
+        Self {
+            retry_after: Some(retry_after),
+            ..self
+        }
+    }
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
-        (self.status, self.to_string()).into_response()
+        let mut res = (self.status, self.to_string()).into_response();
+        if let Some(retry_after) = self.retry_after {
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, retry_after.as_secs().into());
+        }
+        res
     }
 }
 
@@ -41,6 +65,7 @@ impl From<sea_orm::DbErr> for Error {
         return Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             report: Some(eyre::eyre!(value)),
+            ..Default::default()
         };
     }
 }
@@ -50,6 +75,7 @@ impl From<eyre::Report> for Error {
         return Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             report: Some(value),
+            ..Default::default()
         };
     }
 }
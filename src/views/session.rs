@@ -63,6 +63,13 @@ impl Session {
         }
     }
 
+    /// Get the user this session belongs to, failing if there isn't one
+    pub async fn user(&mut self, db: &DatabaseConnection) -> Result<user::Model, super::Error> {
+        self.get(db)
+            .await?
+            .ok_or_else(|| super::forbidden("Invalid session"))
+    }
+
     #[must_use]
     pub async fn add(self, state: &AppState, user_id: Uuid) -> Result<CookieJar, super::Error> {
         let now = Utc::now();
@@ -0,0 +1,68 @@
+use chrono::NaiveDateTime;
+use entity::submission;
+use sea_orm::ActiveEnum;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::runner::exec::CaseResult;
+
+/// A graded test case, as stored in `submission.results`
+#[derive(Serialize, Deserialize)]
+pub struct SubmissionCase {
+    pub hidden: bool,
+    #[serde(flatten)]
+    pub result: CaseResult,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SubmissionCaseView {
+    Visible(SubmissionCase),
+    Hidden { hidden: bool, passed: bool },
+}
+
+#[derive(Serialize)]
+pub struct SubmissionView {
+    pub submission_id: Uuid,
+    pub exercise_id: Uuid,
+    pub language: String,
+    pub code: String,
+    pub created: NaiveDateTime,
+    pub status: String,
+    pub passed: usize,
+    pub cases: Vec<SubmissionCaseView>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl SubmissionView {
+    /// View a submission, only revealing hidden test cases if `show_hidden` is set
+    pub fn new(
+        submission: &submission::Model,
+        show_hidden: bool,
+    ) -> Result<Self, serde_json::Error> {
+        let cases: Vec<SubmissionCase> = serde_json::from_value(submission.results.clone())?;
+
+        Ok(Self {
+            submission_id: submission.submission_id,
+            exercise_id: submission.exercise_id,
+            language: submission.language.clone(),
+            code: submission.code.clone(),
+            created: submission.created,
+            status: submission.status.to_value(),
+            passed: cases.iter().filter(|c| c.result.passed).count(),
+            cases: cases
+                .into_iter()
+                .map(|c| match c.hidden && !show_hidden {
+                    true => SubmissionCaseView::Hidden {
+                        hidden: true,
+                        passed: c.result.passed,
+                    },
+                    false => SubmissionCaseView::Visible(c),
+                })
+                .collect(),
+            stdout: submission.stdout.clone(),
+            stderr: submission.stderr.clone(),
+        })
+    }
+}